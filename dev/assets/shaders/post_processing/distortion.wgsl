@@ -0,0 +1,49 @@
+const WOBBLE_AMPLITUDE = 0.004;
+const WOBBLE_FREQUENCY = 30.0;
+const WOBBLE_SPEED = 2.0;
+const CHROMATIC_OFFSET = 0.006;
+const MAX_DESATURATION = 0.7;
+const PALETTE_TINT = vec3(0.55, 0.75, 0.6);
+const LUMINANCE = vec3(0.299, 0.587, 0.114);
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+struct DistortionShaderSettings {
+    time: f32,
+    intensity: f32,
+#ifdef SIXTEEN_BYTE_ALIGNMENT
+    // WebGL2 structs must be 16 byte aligned.
+    _webgl2_padding: vec2<f32>
+#endif
+}
+@group(0) @binding(2) var<uniform> settings: DistortionShaderSettings;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let intensity: f32 = clamp(settings.intensity, 0.0, 1.0);
+
+    // wobble, only past half intensity
+    let wobble: f32 = smoothstep(0.5, 1.0, intensity);
+    var uv: vec2<f32> = in.uv;
+    uv.x += sin(uv.y * WOBBLE_FREQUENCY + settings.time * WOBBLE_SPEED) * WOBBLE_AMPLITUDE * wobble;
+
+    // chromatic offset
+    let offset: vec2<f32> = vec2<f32>(CHROMATIC_OFFSET * intensity, 0.0);
+    let r: f32 = textureSample(screen_texture, texture_sampler, uv + offset).r;
+    let g: f32 = textureSample(screen_texture, texture_sampler, uv).g;
+    let b: f32 = textureSample(screen_texture, texture_sampler, uv - offset).b;
+    var color: vec3<f32> = vec3<f32>(r, g, b);
+
+    // palette shift and desaturation
+    let luminance: f32 = dot(color, LUMINANCE);
+    color = mix(color, luminance * PALETTE_TINT, intensity * MAX_DESATURATION);
+
+    // vignette
+    let distToCenter: f32 = distance(in.uv, vec2<f32>(0.5, 0.5));
+    color *= 1.0 - smoothstep(0.3, 0.75, distToCenter) * intensity;
+
+    // Output to screen
+    return vec4<f32>(color, 1.0);
+}
@@ -2,18 +2,26 @@ use bevy::prelude::*;
 
 mod audio;
 mod camera;
+mod combat;
 mod controls;
 mod custom_asset_types;
 mod dialog_system;
+mod enemy;
 mod global;
+mod items;
+mod journal;
 mod lighting;
+mod madness;
 mod map;
 mod physics;
 mod player;
+mod save;
 mod third_party;
 mod tick;
 mod ui;
 
+pub use custom_asset_types::ink::{SCENARIO_PATH, SCENARIO_SOURCE};
+
 pub fn plugin(app: &mut App) {
     app.add_plugins((
         global::plugin,
@@ -29,5 +37,13 @@ pub fn plugin(app: &mut App) {
         tick::plugin,
         controls::plugin,
         player::plugin,
+        save::plugin,
+        (
+            items::plugin,
+            journal::plugin,
+            madness::plugin,
+            enemy::plugin,
+            combat::plugin,
+        ),
     ));
 }
@@ -3,22 +3,29 @@ use std::collections::HashMap;
 use bevy::prelude::*;
 use bevy_kira_audio::{AudioApp, AudioChannel, AudioControl, SpatialAudioEmitter, SpatialRadius};
 
-mod dummy_npc;
-
-const DEFAULT_RADIUS: f32 = 150.;
+pub const DEFAULT_RADIUS: f32 = 150.;
 
 #[derive(Resource)]
 pub struct SpatialAudioChannel;
 
-pub trait SpatialAudioParameters {
-    fn file_path(audio_id: String) -> Option<String> {
-        Self::file_paths().get(&audio_id).cloned()
-    }
+/// Sounds an object can play, keyed by audio id.
+/// The `default` audio loops as soon as the object is spawned
+#[derive(Component, Clone, Debug)]
+pub struct SpatialAudioParameters {
+    pub file_paths: HashMap<String, String>,
+    pub radius: f32,
+}
 
-    fn file_paths() -> HashMap<String, String>;
+impl SpatialAudioParameters {
+    pub fn new(file_paths: HashMap<String, String>) -> Self {
+        Self {
+            file_paths,
+            radius: DEFAULT_RADIUS,
+        }
+    }
 
-    fn radius() -> f32 {
-        DEFAULT_RADIUS
+    pub fn file_path(&self, audio_id: &str) -> Option<&String> {
+        self.file_paths.get(audio_id)
     }
 }
 
@@ -31,19 +38,22 @@ pub struct PlayObjectAudio {
 pub fn plugin(app: &mut App) {
     app.add_audio_channel::<SpatialAudioChannel>();
     app.add_message::<PlayObjectAudio>();
-    app.add_plugins(dummy_npc::plugin);
+    app.add_systems(
+        Update,
+        (setup_spatial_object_audio, queue_object_audio).chain(),
+    );
 }
 
-pub fn setup_spatial_object_audio<T: Component + SpatialAudioParameters>(
+fn setup_spatial_object_audio(
     mut commands: Commands,
     spatial_audio_channel: Res<AudioChannel<SpatialAudioChannel>>,
     asset_server: Res<AssetServer>,
-    spatial_objects: Query<Entity, Added<T>>,
+    spatial_objects: Query<(Entity, &SpatialAudioParameters), Added<SpatialAudioParameters>>,
 ) {
-    for entity in spatial_objects {
+    for (entity, parameters) in spatial_objects {
         let mut audio_instances = vec![];
 
-        if let Some(file_path) = T::file_path("default".into()) {
+        if let Some(file_path) = parameters.file_path("default") {
             audio_instances.push(
                 spatial_audio_channel
                     .play(asset_server.load(file_path))
@@ -57,21 +67,21 @@ pub fn setup_spatial_object_audio<T: Component + SpatialAudioParameters>(
                 instances: audio_instances,
             },
             SpatialRadius {
-                radius: T::radius(),
+                radius: parameters.radius,
             },
         ));
     }
 }
 
-pub fn queue_object_audio<T: Component + SpatialAudioParameters>(
+fn queue_object_audio(
     mut events: MessageReader<PlayObjectAudio>,
     spatial_audio_channel: Res<AudioChannel<SpatialAudioChannel>>,
     asset_server: Res<AssetServer>,
-    mut spatial_objects: Query<&mut SpatialAudioEmitter, With<T>>,
+    mut spatial_objects: Query<(&mut SpatialAudioEmitter, &SpatialAudioParameters)>,
 ) {
     for event in events.read() {
-        if let Ok(mut audio_emitter) = spatial_objects.get_mut(event.entity)
-            && let Some(file_path) = T::file_path(event.audio_id.clone())
+        if let Ok((mut audio_emitter, parameters)) = spatial_objects.get_mut(event.entity)
+            && let Some(file_path) = parameters.file_path(&event.audio_id)
         {
             let audio = spatial_audio_channel
                 .play(asset_server.load(file_path))
@@ -1,9 +1,12 @@
 use bevy::prelude::*;
 
-use crate::game::camera::post_processing_shaders::level_transition_shader::{
-    self, LevelTransitionShaderSettings,
+use crate::game::camera::post_processing_shaders::{
+    distortion_shader::{self, DistortionShaderSettings},
+    level_transition_shader::{self, LevelTransitionShaderSettings},
 };
 
+pub use post_processing_shaders::distortion_shader::DistortionIntensity;
+
 mod post_processing_shaders;
 
 /// Component used to identify main camera
@@ -17,12 +20,17 @@ pub struct MainCamera;
 pub struct CameraTarget;
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins(level_transition_shader::plugin);
+    app.add_plugins((
+        distortion_shader::plugin,
+        level_transition_shader::plugin,
+        post_processing_shaders::order_plugin,
+    ));
     app.add_systems(Startup, spawn_camera);
     app.add_systems(Update, lock_camera_on_target);
 }
 
-/// Spawning a startup a camera with a fullscreen shader that triggers every time the player is teleported to another level
+/// Spawning a startup a camera with a fullscreen shader that triggers every time the player is teleported to another level,
+/// under a distortion shader following [`DistortionIntensity`]
 pub fn spawn_camera(mut commands: Commands, camera_target: Query<&Transform, With<CameraTarget>>) {
     let target_transform = *camera_target.single().unwrap_or(&Transform {
         ..Default::default()
@@ -44,6 +52,7 @@ pub fn spawn_camera(mut commands: Commands, camera_target: Query<&Transform, Wit
             time: 0.0,
             ..default()
         },
+        DistortionShaderSettings::default(),
         Transform::from_translation(target_transform.translation),
     ));
 }
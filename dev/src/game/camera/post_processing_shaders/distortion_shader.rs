@@ -0,0 +1,109 @@
+#![allow(unexpected_cfgs)]
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    render::{extract_component::ExtractComponent, render_resource::ShaderType},
+};
+use bevy_tweening::{Lens, Tween, TweenAnim};
+
+use crate::game::{
+    dialog_system::external_functions::{InkCall, InkExternalFunctions},
+    tick::TickDelta,
+};
+
+const SHADER_ASSET_PATH: &str = "shaders/post_processing/distortion.wgsl";
+/// Ink external function: `~ set_distortion(0.5)`
+const SET_DISTORTION: &str = "set_distortion";
+
+// This is the component that will get passed to the shader
+#[derive(Component, Default, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct DistortionShaderSettings {
+    /// Drives the wobble animation
+    pub time: f32,
+    /// From 0 (no effect) to 1, tweened towards [`DistortionIntensity`]
+    pub intensity: f32,
+    // WebGL2 structs must be 16 byte aligned.
+    #[cfg(feature = "webgl2")]
+    _webgl2_padding: Vec2,
+}
+
+impl super::ShaderAsset for DistortionShaderSettings {
+    fn shader_asset_path() -> String {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn label() -> super::PostProcessLabel {
+        super::PostProcessLabel::Distortion
+    }
+}
+
+/// How distorted the screen should look, from 0 to 1.
+/// Set by zones, dialogs and the madness of the player, the shader catches up over a measure
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub struct DistortionIntensity {
+    pub value: f32,
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(super::plugin::<DistortionShaderSettings>);
+    app.init_resource::<DistortionIntensity>();
+    app.add_systems(Startup, register_ink_functions);
+    app.add_systems(Update, (tween_intensity, update_time));
+}
+
+fn register_ink_functions(mut external_functions: ResMut<InkExternalFunctions>) {
+    external_functions.register(SET_DISTORTION, set_distortion);
+}
+
+fn set_distortion(call: &InkCall, commands: &mut Commands) {
+    if let Some(value) = call.float(0) {
+        commands.insert_resource(DistortionIntensity {
+            value: value.clamp(0., 1.),
+        });
+    } else {
+        warn!("{} expects an intensity", SET_DISTORTION);
+    }
+}
+
+struct DistortionIntensityLens {
+    start: f32,
+    end: f32,
+}
+
+impl Lens<DistortionShaderSettings> for DistortionIntensityLens {
+    fn lerp(&mut self, mut target: Mut<DistortionShaderSettings>, ratio: f32) {
+        target.intensity = self.start + (self.end - self.start) * ratio;
+    }
+}
+
+fn tween_intensity(
+    mut commands: Commands,
+    intensity: Res<DistortionIntensity>,
+    tick_delta: Res<TickDelta>,
+    settings: Query<(Entity, &DistortionShaderSettings)>,
+) {
+    if !intensity.is_changed() {
+        return;
+    }
+
+    for (entity, setting) in settings {
+        let tween = Tween::new(
+            EaseFunction::SineInOut,
+            Duration::from_secs_f32(tick_delta.measure),
+            DistortionIntensityLens {
+                start: setting.intensity,
+                end: intensity.value,
+            },
+        );
+
+        commands.entity(entity).try_insert(TweenAnim::new(tween));
+    }
+}
+
+fn update_time(mut settings: Query<&mut DistortionShaderSettings>, time: Res<Time>) {
+    for mut setting in &mut settings {
+        setting.time += time.delta_secs();
+    }
+}
@@ -28,6 +28,10 @@ impl super::ShaderAsset for LevelTransitionShaderSettings {
     fn shader_asset_path() -> String {
         SHADER_ASSET_PATH.into()
     }
+
+    fn label() -> super::PostProcessLabel {
+        super::PostProcessLabel::LevelTransition
+    }
 }
 
 pub fn plugin(app: &mut App) {
@@ -26,10 +26,20 @@ use bevy::{
     },
 };
 
+pub mod distortion_shader;
 pub mod level_transition_shader;
 
 pub trait ShaderAsset {
     fn shader_asset_path() -> String;
+    /// Render graph node of the effect, ordered by [`order_plugin`]
+    fn label() -> PostProcessLabel;
+}
+
+/// One label per effect, so that several of them can be stacked on the same camera
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, RenderLabel)]
+pub enum PostProcessLabel {
+    Distortion,
+    LevelTransition,
 }
 
 pub fn plugin<
@@ -78,22 +88,29 @@ pub fn plugin<
             // Specify the label of the graph, in this case we want the graph for 3d
             Core2d,
             // It also needs the label of the node
-            PostProcessLabel,
-        )
-        .add_render_graph_edges(
-            Core2d,
-            // Specify the node ordering.
-            // This will automatically create all required node edges to enforce the given ordering.
-            (
-                Node2d::Tonemapping,
-                PostProcessLabel,
-                Node2d::EndMainPassPostProcessing,
-            ),
+            T::label(),
         );
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
-struct PostProcessLabel;
+/// Must be added after the plugin of every effect, as edges can only link existing nodes
+pub fn order_plugin(app: &mut App) {
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+        return;
+    };
+
+    render_app.add_render_graph_edges(
+        Core2d,
+        // Specify the node ordering.
+        // This will automatically create all required node edges to enforce the given ordering.
+        // The level transition comes last so that it covers every other effect.
+        (
+            Node2d::Tonemapping,
+            PostProcessLabel::Distortion,
+            PostProcessLabel::LevelTransition,
+            Node2d::EndMainPassPostProcessing,
+        ),
+    );
+}
 
 // The post process node used for the render graph
 #[derive(Default)]
@@ -135,7 +152,7 @@ impl<
     ) -> Result<(), NodeRunError> {
         // Get the pipeline resource that contains the global data we need
         // to create the render pipeline
-        let post_process_pipeline = world.resource::<PostProcessPipeline>();
+        let post_process_pipeline = world.resource::<PostProcessPipeline<T>>();
 
         // The pipeline cache is a cache of all previously created pipelines.
         // It is required to avoid creating a new pipeline each frame,
@@ -214,11 +231,13 @@ impl<
 }
 
 // This contains global data used by the render pipeline. This will be created once on startup.
+// Each effect gets its own, as the settings uniform differs.
 #[derive(Resource)]
-struct PostProcessPipeline {
+struct PostProcessPipeline<T> {
     layout: BindGroupLayout,
     sampler: Sampler,
     pipeline_id: CachedRenderPipelineId,
+    marker: PhantomData<T>,
 }
 
 fn init_post_process_pipeline<
@@ -272,9 +291,10 @@ fn init_post_process_pipeline<
             }),
             ..default()
         });
-    commands.insert_resource(PostProcessPipeline {
+    commands.insert_resource(PostProcessPipeline::<T> {
         layout,
         sampler,
         pipeline_id,
+        marker: PhantomData,
     });
 }
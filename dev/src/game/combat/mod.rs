@@ -0,0 +1,338 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+
+use crate::game::{
+    controls::{PlayerAction, PlayerInputs},
+    enemy::{DEFEATED_FLAG, Enemy, EnemyEngaged, EnemyState},
+    global::GameState,
+    madness::{ChangeMadness, MAX_MADNESS},
+    map::{InstanceIid, world_state::WorldState},
+    physics::pathfinding::PathFollower,
+    player::{Player, player_roaming},
+    tick::{GameTempo, MainTick, MainTickCounter, TickDelta},
+};
+
+const PLAYER_HEALTH: i32 = 5;
+/// Damage of an enemy hit, every measure
+const ENEMY_DAMAGE: i32 = 1;
+/// Fractions of a note between the input and the closest tick
+const PERFECT_WINDOW: f32 = 0.1;
+const GOOD_WINDOW: f32 = 0.25;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Health {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn damage(&mut self, amount: i32) {
+        self.current = (self.current - amount).max(0);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Judgement {
+    Perfect,
+    Good,
+    Miss,
+}
+
+impl Judgement {
+    /// `offset` being the seconds between the input and the closest tick
+    pub fn from_offset(offset: f32, note: f32) -> Self {
+        match offset.abs() / note {
+            ratio if ratio <= PERFECT_WINDOW => Self::Perfect,
+            ratio if ratio <= GOOD_WINDOW => Self::Good,
+            _ => Self::Miss,
+        }
+    }
+
+    pub fn damage(&self) -> i32 {
+        match self {
+            Self::Perfect => 2,
+            Self::Good => 1,
+            Self::Miss => 0,
+        }
+    }
+}
+
+/// Seconds between now and the closest tick of the timer, before or after it
+pub fn beat_offset(timer: &Timer) -> f32 {
+    let elapsed = timer.elapsed_secs();
+
+    elapsed.min(timer.duration().as_secs_f32() - elapsed)
+}
+
+/// Enemy currently fought, the player can't move until the fight ends
+#[derive(Resource, Default, Debug)]
+pub struct Combat {
+    pub enemy: Option<Entity>,
+    /// Elapsed seconds at the last attack, a single attack is judged per note
+    last_attack: Option<f32>,
+}
+
+/// Pauses the state machine of the enemy being fought
+#[derive(Component)]
+pub struct Fighting;
+
+#[derive(Message)]
+pub struct CombatStarted {
+    pub enemy: Entity,
+}
+
+#[derive(Message)]
+pub struct CombatEnded {
+    pub victory: bool,
+}
+
+#[derive(Message)]
+pub struct AttackJudged {
+    pub judgement: Judgement,
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Combat>();
+    app.add_message::<CombatStarted>();
+    app.add_message::<CombatEnded>();
+    app.add_message::<AttackJudged>();
+    app.add_systems(
+        Update,
+        (
+            add_player_health,
+            start_combat.run_if(player_roaming),
+            judge_attacks,
+            enemies_attack,
+            end_combat,
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn add_player_health(
+    mut commands: Commands,
+    players: Query<Entity, (Added<Player>, Without<Health>)>,
+) {
+    for player in players {
+        commands.entity(player).insert(Health::new(PLAYER_HEALTH));
+    }
+}
+
+fn start_combat(
+    mut commands: Commands,
+    mut events: MessageReader<EnemyEngaged>,
+    mut combat_started: MessageWriter<CombatStarted>,
+    mut combat: ResMut<Combat>,
+    mut enemies: Query<(&GridCoords, &mut PathFollower), With<Enemy>>,
+) {
+    for event in events.read() {
+        if combat.enemy.is_some() {
+            continue;
+        }
+
+        if let Ok((grid_coords, mut follower)) = enemies.get_mut(event.enemy) {
+            follower.go_to(*grid_coords);
+        }
+
+        commands.entity(event.enemy).insert(Fighting);
+        combat.enemy = Some(event.enemy);
+        combat.last_attack = None;
+
+        info!("Combat started");
+
+        combat_started.write(CombatStarted { enemy: event.enemy });
+    }
+}
+
+fn judge_attacks(
+    keys: Res<PlayerInputs>,
+    mut attack_judged: MessageWriter<AttackJudged>,
+    mut combat: ResMut<Combat>,
+    main_tick: Res<MainTick>,
+    tick_delta: Res<TickDelta>,
+    time: Res<Time>,
+    mut enemies: Query<&mut Health, With<Enemy>>,
+) {
+    let Some(enemy) = combat.enemy else {
+        return;
+    };
+
+    if !keys.just_pressed_actions.contains(&PlayerAction::Activate) {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+
+    // Mashing the button misses
+    let judgement = match combat.last_attack {
+        Some(last_attack) if now - last_attack < tick_delta.note / 2. => Judgement::Miss,
+        _ => Judgement::from_offset(beat_offset(&main_tick.timer), tick_delta.note),
+    };
+
+    combat.last_attack = Some(now);
+
+    if let Ok(mut health) = enemies.get_mut(enemy) {
+        health.damage(judgement.damage());
+    }
+
+    attack_judged.write(AttackJudged { judgement });
+}
+
+fn enemies_attack(
+    combat: Res<Combat>,
+    main_tick: Res<MainTick>,
+    main_tick_counter: Res<MainTickCounter>,
+    game_tempo: Res<GameTempo>,
+    mut players: Query<&mut Health, (With<Player>, Without<Enemy>)>,
+) {
+    if combat.enemy.is_none()
+        || !main_tick.timer.just_finished()
+        || !main_tick_counter.on_measure(&game_tempo)
+    {
+        return;
+    }
+
+    for mut health in &mut players {
+        health.damage(ENEMY_DAMAGE);
+    }
+}
+
+fn end_combat(
+    mut commands: Commands,
+    mut combat_ended: MessageWriter<CombatEnded>,
+    mut change_madness: MessageWriter<ChangeMadness>,
+    mut combat: ResMut<Combat>,
+    mut world_state: ResMut<WorldState>,
+    mut players: Query<&mut Health, (With<Player>, Without<Enemy>)>,
+    mut enemies: Query<(&Health, &mut EnemyState, &InstanceIid), With<Enemy>>,
+) {
+    let Some(enemy) = combat.enemy else {
+        return;
+    };
+
+    let player_dead = players.iter().any(|health| health.is_dead());
+
+    let victory = match enemies.get_mut(enemy) {
+        Ok((health, _, iid)) if health.is_dead() => {
+            // Not respawned when the level is reloaded
+            world_state
+                .entities
+                .entry(iid.0.clone())
+                .or_default()
+                .flags
+                .get_or_insert_default()
+                .insert(DEFEATED_FLAG.into(), true);
+
+            commands.entity(enemy).despawn();
+            true
+        }
+        Ok((_, mut state, _)) if player_dead => {
+            commands.entity(enemy).remove::<Fighting>();
+            *state = EnemyState::Idle;
+            false
+        }
+        // Despawned with its level
+        Err(_) => false,
+        Ok(_) => return,
+    };
+
+    if player_dead {
+        for mut health in &mut players {
+            *health = Health::new(health.max);
+        }
+
+        // Enough to faint from any phase, waking up at the last checkpoint
+        change_madness.write(ChangeMadness {
+            amount: MAX_MADNESS * 2.,
+        });
+    }
+
+    combat.enemy = None;
+
+    info!("Combat ended, victory: {}", victory);
+
+    combat_ended.write(CombatEnded { victory });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn tick_main_tick(time: Res<Time>, mut main_tick: ResMut<MainTick>) {
+        main_tick.timer.tick(time.delta());
+    }
+
+    #[test]
+    fn judges_offsets_against_notes() {
+        assert_eq!(Judgement::from_offset(0.04, 0.5), Judgement::Perfect);
+        assert_eq!(Judgement::from_offset(-0.1, 0.5), Judgement::Good);
+        assert_eq!(Judgement::from_offset(0.2, 0.5), Judgement::Miss);
+    }
+
+    #[test]
+    fn judges_attacks_with_simulated_time() {
+        // Setup
+        let mut app = App::new();
+        let tick_delta: TickDelta = GameTempo {
+            bpm: 120.,
+            beats_per_measure: 4.,
+            notes_per_measure: 4.,
+        }
+        .into();
+
+        app.insert_resource(MainTick {
+            timer: Timer::new(
+                Duration::from_secs_f32(tick_delta.note),
+                TimerMode::Repeating,
+            ),
+        });
+        app.insert_resource(tick_delta);
+        app.insert_resource(Time::<()>::default());
+        app.add_message::<AttackJudged>();
+        app.add_systems(Update, (tick_main_tick, judge_attacks).chain());
+
+        let enemy = app
+            .world_mut()
+            .spawn((Enemy::default(), Health::new(5)))
+            .id();
+        app.insert_resource(Combat {
+            enemy: Some(enemy),
+            last_attack: None,
+        });
+
+        let mut keys = PlayerInputs::default();
+        keys.just_pressed_actions.insert(PlayerAction::Activate);
+        app.insert_resource(keys);
+
+        // Run
+        // 20ms before the second tick of a half second note
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.98));
+        app.update();
+
+        // Check
+        let messages = app.world().resource::<Messages<AttackJudged>>();
+        let judgements: Vec<_> = messages
+            .iter_current_update_messages()
+            .map(|event| event.judgement)
+            .collect();
+
+        assert_eq!(judgements, vec![Judgement::Perfect]);
+        assert_eq!(
+            app.world().get::<Health>(enemy),
+            Some(&Health { current: 3, max: 5 })
+        );
+    }
+}
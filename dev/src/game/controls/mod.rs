@@ -9,6 +9,11 @@ pub enum PlayerAction {
     Left,
     Right,
     Activate,
+    DialogLog,
+    Journal,
+    Inventory,
+    QuickSave,
+    QuickLoad,
 }
 
 impl PlayerAction {
@@ -19,6 +24,11 @@ impl PlayerAction {
             PlayerAction::Left,
             PlayerAction::Right,
             PlayerAction::Activate,
+            PlayerAction::DialogLog,
+            PlayerAction::Journal,
+            PlayerAction::Inventory,
+            PlayerAction::QuickSave,
+            PlayerAction::QuickLoad,
         ]
     }
 
@@ -29,6 +39,11 @@ impl PlayerAction {
             PlayerAction::Left => KeyCode::KeyA,
             PlayerAction::Right => KeyCode::KeyD,
             PlayerAction::Activate => KeyCode::Space,
+            PlayerAction::DialogLog => KeyCode::Tab,
+            PlayerAction::Journal => KeyCode::KeyJ,
+            PlayerAction::Inventory => KeyCode::KeyI,
+            PlayerAction::QuickSave => KeyCode::F5,
+            PlayerAction::QuickLoad => KeyCode::F9,
         }
     }
 
@@ -39,6 +54,11 @@ impl PlayerAction {
             PlayerAction::Left => GamepadButton::DPadLeft,
             PlayerAction::Right => GamepadButton::DPadRight,
             PlayerAction::Activate => GamepadButton::South,
+            PlayerAction::DialogLog => GamepadButton::Select,
+            PlayerAction::Journal => GamepadButton::North,
+            PlayerAction::Inventory => GamepadButton::West,
+            PlayerAction::QuickSave => GamepadButton::Start,
+            PlayerAction::QuickLoad => GamepadButton::Mode,
         }
     }
 }
@@ -0,0 +1,191 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader, io::file::FileAssetReader},
+    prelude::*,
+};
+use thiserror::Error;
+
+use crate::game::custom_asset_types::ink_json::InkJson;
+
+/// Asset source holding the writers' files, loaded as `scenario://dialogs/...`
+pub const SCENARIO_SOURCE: &str = "scenario";
+/// Path of the scenario folder, relative to the assets base path
+pub const SCENARIO_PATH: &str = "../scenario";
+/// Ink compiler, expected in the `PATH`
+const INK_COMPILER: &str = "inklecate";
+
+/// Compiles `.ink` files with `inklecate` into [`InkJson`] assets
+#[derive(Default)]
+struct InkAssetLoader;
+
+/// Possible errors that can be produced by [`InkAssetLoader`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum InkCompileError {
+    /// An [IO](std::io) Error
+    #[error("Could not load file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not run {INK_COMPILER}, is it installed? {0}")]
+    Compiler(std::io::Error),
+    /// Compiler errors, each of them with the file and line at fault
+    #[error(
+        "Could not compile ink:\n{}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+    )]
+    Compilation(Vec<InkSourceError>),
+}
+
+/// Error reported by the compiler as `ERROR: 'file.ink' line 12: message`
+#[derive(Debug, PartialEq)]
+pub struct InkSourceError {
+    pub file: String,
+    /// Missing when the error is not about a line, such as an unreadable file
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for InkSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl AssetLoader for InkAssetLoader {
+    type Asset = InkJson;
+    type Settings = ();
+    type Error = InkCompileError;
+
+    async fn load(
+        &self,
+        _reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        info!("Compiling Ink...");
+
+        // The compiler reads the file itself so that `INCLUDE`s are resolved from its folder
+        let path = FileAssetReader::get_base_path()
+            .join(SCENARIO_PATH)
+            .join(load_context.path());
+
+        Ok(InkJson {
+            string: compile_ink(&path)?,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ink"]
+    }
+}
+
+/// Compiles an `.ink` file and returns its JSON
+pub fn compile_ink(path: &Path) -> Result<String, InkCompileError> {
+    let output_path = compiled_path(path);
+
+    let output = Command::new(INK_COMPILER)
+        .arg("-o")
+        .arg(&output_path)
+        .arg(path)
+        .output()
+        .map_err(InkCompileError::Compiler)?;
+
+    let errors: Vec<InkSourceError> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .filter_map(|line| parse_compiler_error(line, path))
+        .collect();
+
+    if !output.status.success() || !errors.is_empty() {
+        let _ = fs::remove_file(&output_path);
+
+        return Err(InkCompileError::Compilation(errors));
+    }
+
+    let json = fs::read_to_string(&output_path)?;
+
+    fs::remove_file(&output_path)?;
+
+    // The compiler writes a byte order mark that the JSON parser does not expect
+    Ok(json.trim_start_matches('\u{feff}').into())
+}
+
+/// Parses `ERROR: 'file.ink' line 12: message`, other error lines are attributed to the compiled file
+fn parse_compiler_error(line: &str, path: &Path) -> Option<InkSourceError> {
+    let error = line.strip_prefix("ERROR:")?.trim();
+
+    let located = error
+        .strip_prefix('\'')
+        .and_then(|error| error.split_once("' line "))
+        .and_then(|(file, error)| {
+            let (line, message) = error.split_once(':')?;
+
+            Some(InkSourceError {
+                file: file.into(),
+                line: Some(line.trim().parse().ok()?),
+                message: message.trim().into(),
+            })
+        });
+
+    Some(located.unwrap_or_else(|| InkSourceError {
+        file: path.to_string_lossy().into(),
+        line: None,
+        message: error.into(),
+    }))
+}
+
+/// Unique per source file, as several files can be compiled at the same time
+fn compiled_path(path: &Path) -> PathBuf {
+    let file_name = path.to_string_lossy().replace(['/', '\\', ':'], "_");
+
+    env::temp_dir().join(format!("{}-{}.json", std::process::id(), file_name))
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_asset_loader::<InkAssetLoader>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compiler_errors() {
+        // Setup
+        let path = Path::new("dialogs/dummy_npc.ink");
+
+        // Run
+        let located = parse_compiler_error(
+            "ERROR: 'dialogs/dummy_npc.ink' line 12: Divert target not found: '-> nowhere'",
+            path,
+        );
+        let unlocated = parse_compiler_error("ERROR: Could not open file", path);
+        let warning = parse_compiler_error("WARNING: 'dialogs/dummy_npc.ink' line 3: TODO", path);
+
+        // Check
+        assert_eq!(
+            located,
+            Some(InkSourceError {
+                file: "dialogs/dummy_npc.ink".into(),
+                line: Some(12),
+                message: "Divert target not found: '-> nowhere'".into(),
+            })
+        );
+        assert_eq!(
+            unlocated,
+            Some(InkSourceError {
+                file: "dialogs/dummy_npc.ink".into(),
+                line: None,
+                message: "Could not open file".into(),
+            })
+        );
+        assert_eq!(warning, None);
+    }
+}
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 
+pub mod ink;
 pub mod ink_json;
+pub mod ron_asset;
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins(ink_json::plugin);
+    app.add_plugins((ink_json::plugin, ink::plugin));
 }
@@ -0,0 +1,60 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Data files deserialized from RON, each asset type with its own extension
+/// such as `clues.ron`
+pub trait RonAsset: Asset + DeserializeOwned {
+    const EXTENSIONS: &'static [&'static str];
+}
+
+struct RonAssetLoader<T>(PhantomData<T>);
+
+impl<T> Default for RonAssetLoader<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Possible errors that can be produced by [`RonAssetLoader`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+enum RonAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not read file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl<T: RonAsset> AssetLoader for RonAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        T::EXTENSIONS
+    }
+}
+
+pub fn ron_asset_plugin<T: RonAsset>(app: &mut App) {
+    app.init_asset::<T>();
+    app.init_asset_loader::<RonAssetLoader<T>>();
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DialogLogKind {
+    Line,
+    Choice,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DialogLogEntry {
+    /// Entries sharing this id were part of the same conversation
+    pub conversation: u32,
+    /// Empty for the choices of the player
+    pub speaker: String,
+    pub text: String,
+    pub kind: DialogLogKind,
+}
+
+/// Every line shown and choice made since the run started
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DialogLog {
+    pub entries: Vec<DialogLogEntry>,
+    next_conversation: u32,
+}
+
+impl DialogLog {
+    /// Returns the id of the new conversation
+    pub fn start_conversation(&mut self) -> u32 {
+        let conversation = self.next_conversation;
+
+        self.next_conversation += 1;
+
+        conversation
+    }
+
+    pub fn record_line(&mut self, conversation: u32, speaker: &str, text: &str) {
+        self.entries.push(DialogLogEntry {
+            conversation,
+            speaker: speaker.into(),
+            text: text.trim().into(),
+            kind: DialogLogKind::Line,
+        });
+    }
+
+    pub fn record_choice(&mut self, conversation: u32, text: &str) {
+        self.entries.push(DialogLogEntry {
+            conversation,
+            speaker: String::new(),
+            text: text.trim().into(),
+            kind: DialogLogKind::Choice,
+        });
+    }
+
+    /// Consecutive entries of the same conversation and speaker
+    pub fn grouped_by_speaker(&self) -> Vec<&[DialogLogEntry]> {
+        self.entries
+            .chunk_by(|previous, next| {
+                previous.conversation == next.conversation && previous.speaker == next.speaker
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialog_log() -> DialogLog {
+        let mut dialog_log = DialogLog::default();
+
+        let first = dialog_log.start_conversation();
+        dialog_log.record_line(first, "Dummy Npc", "Once upon a time...");
+        dialog_log.record_line(first, "Dummy Npc", "There were two choices. ");
+        dialog_log.record_choice(first, "Choice 1");
+        dialog_log.record_line(first, "Dummy Npc", "You chose the first.");
+
+        let second = dialog_log.start_conversation();
+        dialog_log.record_line(second, "Dummy Npc", "Hello again.");
+
+        dialog_log
+    }
+
+    #[test]
+    fn groups_entries_by_speaker_and_conversation() {
+        // Setup
+        let dialog_log = dialog_log();
+
+        // Run
+        let groups: Vec<Vec<&str>> = dialog_log
+            .grouped_by_speaker()
+            .iter()
+            .map(|group| group.iter().map(|entry| entry.text.as_str()).collect())
+            .collect();
+
+        // Check
+        assert_eq!(
+            groups,
+            vec![
+                vec!["Once upon a time...", "There were two choices."],
+                vec!["Choice 1"],
+                vec!["You chose the first."],
+                vec!["Hello again."],
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_conversation_ids_across_saves() {
+        // Setup
+        let dialog_log = dialog_log();
+
+        // Run
+        let content = ron::to_string(&dialog_log).unwrap();
+        let mut loaded: DialogLog = ron::from_str(&content).unwrap();
+        let conversation = loaded.start_conversation();
+
+        // Check
+        assert_eq!(loaded.entries, dialog_log.entries);
+        assert_eq!(conversation, 2);
+    }
+}
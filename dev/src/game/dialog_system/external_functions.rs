@@ -0,0 +1,369 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use bevy::prelude::*;
+use bladeink::{
+    story::{Story, external_functions::ExternalFunction},
+    value_type::ValueType,
+};
+
+use crate::game::{
+    audio::{music::PlaySong, object_audio::PlayObjectAudio},
+    dialog_system::story_variables::StoryValue,
+    map::ChangeLevel,
+    player::Teleported,
+};
+
+/// A call made by a story to one of its `EXTERNAL` functions
+pub struct InkCall {
+    pub name: String,
+    pub arguments: Vec<StoryValue>,
+    /// Entity the running dialog belongs to
+    pub source_entity: Entity,
+    pub player_entity: Option<Entity>,
+}
+
+impl InkCall {
+    pub fn string(&self, index: usize) -> Option<&str> {
+        match self.arguments.get(index) {
+            Some(StoryValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, index: usize) -> Option<i32> {
+        match self.arguments.get(index) {
+            Some(StoryValue::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, index: usize) -> Option<f32> {
+        match self.arguments.get(index) {
+            Some(StoryValue::Float(value)) => Some(*value),
+            Some(StoryValue::Int(value)) => Some(*value as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Turns an Ink call into game actions, usually by writing messages
+pub type InkFunctionHandler = fn(&InkCall, &mut Commands);
+
+/// Calls recorded while a story runs, dispatched once the dialog step is over
+#[derive(Default, Clone)]
+pub struct InkCallQueue(Rc<RefCell<Vec<(String, Vec<StoryValue>)>>>);
+
+impl InkCallQueue {
+    pub fn drain(&self) -> Vec<(String, Vec<StoryValue>)> {
+        self.0.borrow_mut().drain(..).collect()
+    }
+}
+
+struct QueuedFunction {
+    queue: InkCallQueue,
+}
+
+impl ExternalFunction for QueuedFunction {
+    fn call(&mut self, func_name: &str, args: Vec<ValueType>) -> Option<ValueType> {
+        self.queue.0.borrow_mut().push((
+            func_name.into(),
+            args.iter().filter_map(StoryValue::from_ink).collect(),
+        ));
+
+        None
+    }
+}
+
+/// Values that Ink query functions look up, keyed by function name.
+/// `has_clue("letter")` returns whether `letter` is in the `has_clue` set
+#[derive(Resource, Default, Clone, Debug)]
+pub struct InkQueries {
+    sets: HashMap<String, HashSet<String>>,
+}
+
+impl InkQueries {
+    pub fn set(&mut self, function_name: &str, values: impl IntoIterator<Item = String>) {
+        self.sets
+            .insert(function_name.into(), values.into_iter().collect());
+    }
+}
+
+/// Copy of [`InkQueries`] read by a story, refreshed before each dialog step
+#[derive(Default, Clone)]
+pub struct InkQuerySnapshot(Rc<RefCell<HashMap<String, HashSet<String>>>>);
+
+impl InkQuerySnapshot {
+    pub fn update(&self, queries: &InkQueries) {
+        *self.0.borrow_mut() = queries.sets.clone();
+    }
+}
+
+struct QueryFunction {
+    snapshot: InkQuerySnapshot,
+}
+
+impl ExternalFunction for QueryFunction {
+    fn call(&mut self, func_name: &str, args: Vec<ValueType>) -> Option<ValueType> {
+        let found = args
+            .first()
+            .and_then(|arg| arg.get::<&str>())
+            .is_some_and(|value| {
+                self.snapshot
+                    .0
+                    .borrow()
+                    .get(func_name)
+                    .is_some_and(|values| values.contains(value))
+            });
+
+        Some(ValueType::Bool(found))
+    }
+}
+
+/// Ink external functions available to every story.
+/// Register custom ones from a `Startup` system
+#[derive(Resource)]
+pub struct InkExternalFunctions {
+    handlers: HashMap<String, InkFunctionHandler>,
+    queries: HashSet<String>,
+}
+
+impl Default for InkExternalFunctions {
+    fn default() -> Self {
+        let mut functions = Self {
+            handlers: HashMap::new(),
+            queries: HashSet::new(),
+        };
+
+        functions.register("play_song", play_song);
+        functions.register("change_level", change_level);
+        functions.register("play_object_audio", play_object_audio);
+        functions.register("teleport", teleport);
+
+        functions
+    }
+}
+
+impl InkExternalFunctions {
+    pub fn register(&mut self, name: impl Into<String>, handler: InkFunctionHandler) {
+        self.handlers.insert(name.into(), handler);
+    }
+
+    /// Registers a function answering from the [`InkQueries`] set of the same name
+    pub fn register_query(&mut self, name: impl Into<String>) {
+        self.queries.insert(name.into());
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name) || self.queries.contains(name)
+    }
+
+    /// Binds the given functions so that their calls are recorded in the queue,
+    /// while queries answer right away from the snapshot
+    pub fn bind(
+        &self,
+        story: &mut Story,
+        names: &[String],
+        queue: &InkCallQueue,
+        snapshot: &InkQuerySnapshot,
+    ) -> Result<(), String> {
+        for name in names {
+            let result = if self.queries.contains(name) {
+                story.bind_external_function(
+                    name,
+                    Rc::new(RefCell::new(QueryFunction {
+                        snapshot: snapshot.clone(),
+                    })),
+                    true,
+                )
+            } else {
+                story.bind_external_function(
+                    name,
+                    Rc::new(RefCell::new(QueuedFunction {
+                        queue: queue.clone(),
+                    })),
+                    false,
+                )
+            };
+
+            result.map_err(|err| format!("{:?}", err))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn dispatch(&self, call: &InkCall, commands: &mut Commands) {
+        if let Some(handler) = self.handlers.get(&call.name) {
+            handler(call, commands);
+        }
+    }
+}
+
+fn play_song(call: &InkCall, commands: &mut Commands) {
+    if let (Some(song_title), Some(part)) = (call.string(0), call.string(1)) {
+        commands.write_message(PlaySong {
+            song_title: song_title.into(),
+            part: part.into(),
+        });
+    } else {
+        warn!("play_song expects a song title and a part");
+    }
+}
+
+fn change_level(call: &InkCall, commands: &mut Commands) {
+    if let Some(identifier) = call.string(0) {
+        commands.write_message(ChangeLevel {
+            identifier: identifier.into(),
+            arrival: None,
+        });
+    } else {
+        warn!("change_level expects a level identifier");
+    }
+}
+
+fn play_object_audio(call: &InkCall, commands: &mut Commands) {
+    if let Some(audio_id) = call.string(0) {
+        commands.write_message(PlayObjectAudio {
+            entity: call.source_entity,
+            audio_id: audio_id.into(),
+        });
+    } else {
+        warn!("play_object_audio expects an audio id");
+    }
+}
+
+fn teleport(call: &InkCall, commands: &mut Commands) {
+    if let (Some(player), Some(x), Some(y)) = (call.player_entity, call.int(0), call.int(1)) {
+        commands.write_message(Teleported {
+            entity: player,
+            grid_coords: ivec2(x, y),
+            facing: None,
+        });
+    } else {
+        warn!("teleport expects grid coordinates");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::dialog_system::{
+        DialogError,
+        utils::{get_lines, get_story_with_state},
+    };
+
+    /// Compiled form of the Ink below, written by hand so that the tests do not need `inklecate`:
+    /// ```ink
+    /// EXTERNAL play_song(title, part)
+    /// EXTERNAL has_clue(id)
+    /// ~ play_song("intro", "a")
+    /// {has_clue("letter")}
+    /// ```
+    const STORY: &str = r#"{"inkVersion":21,"root":[["ev","str","^intro","/str","str","^a","/str",{"x()":"play_song","exArgs":2},"pop","/ev","ev","str","^letter","/str",{"x()":"has_clue","exArgs":1},"out","/ev","\n","done",null],"done",null],"listDefs":{}}"#;
+
+    fn run_story(
+        external_functions: &InkExternalFunctions,
+        queue: &InkCallQueue,
+        snapshot: &InkQuerySnapshot,
+    ) -> Result<Vec<String>, DialogError> {
+        let mut story = get_story_with_state(STORY, "", "", external_functions, queue, snapshot)?;
+
+        Ok(get_lines(&mut story)
+            .into_iter()
+            .map(|(line, _)| line.trim().to_string())
+            .collect())
+    }
+
+    #[test]
+    fn rejects_unknown_functions() {
+        // Setup
+        let external_functions = InkExternalFunctions::default();
+
+        // Run
+        let lines = run_story(
+            &external_functions,
+            &InkCallQueue::default(),
+            &InkQuerySnapshot::default(),
+        );
+
+        // Check
+        assert!(matches!(
+            lines,
+            Err(DialogError::UnknownExternalFunctions(names)) if names == vec!["has_clue".to_string()]
+        ));
+    }
+
+    #[test]
+    fn queues_and_dispatches_calls() {
+        // Setup
+        let mut app = App::new();
+        app.add_message::<PlaySong>();
+        let mut external_functions = InkExternalFunctions::default();
+        external_functions.register_query("has_clue");
+        let queue = InkCallQueue::default();
+
+        // Run
+        run_story(&external_functions, &queue, &InkQuerySnapshot::default()).unwrap();
+        let calls = queue.drain();
+
+        for (name, arguments) in calls.clone() {
+            let call = InkCall {
+                name,
+                arguments,
+                source_entity: Entity::PLACEHOLDER,
+                player_entity: None,
+            };
+
+            external_functions.dispatch(&call, &mut app.world_mut().commands());
+        }
+
+        app.world_mut().flush();
+
+        // Check
+        assert_eq!(
+            calls,
+            vec![(
+                "play_song".to_string(),
+                vec![
+                    StoryValue::String("intro".into()),
+                    StoryValue::String("a".into())
+                ]
+            )]
+        );
+
+        let songs: Vec<_> = app
+            .world()
+            .resource::<Messages<PlaySong>>()
+            .iter_current_update_messages()
+            .map(|song| (song.song_title.clone(), song.part.clone()))
+            .collect();
+
+        assert_eq!(songs, vec![("intro".to_string(), "a".to_string())]);
+    }
+
+    #[test]
+    fn answers_queries_from_the_snapshot() {
+        // Setup
+        let mut external_functions = InkExternalFunctions::default();
+        external_functions.register_query("has_clue");
+        let mut queries = InkQueries::default();
+        let snapshot = InkQuerySnapshot::default();
+
+        // Run
+        let without_clue =
+            run_story(&external_functions, &InkCallQueue::default(), &snapshot).unwrap();
+
+        queries.set("has_clue", ["letter".to_string()]);
+        snapshot.update(&queries);
+
+        let with_clue =
+            run_story(&external_functions, &InkCallQueue::default(), &snapshot).unwrap();
+
+        // Check
+        assert_eq!(without_clue, vec!["false"]);
+        assert_eq!(with_clue, vec!["true"]);
+    }
+}
@@ -1,13 +1,29 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+};
 
 use bevy::{asset::LoadedFolder, prelude::*};
+use bladeink::story::Story;
+use thiserror::Error;
 
 use crate::game::{
-    custom_asset_types::ink_json::InkJson,
+    custom_asset_types::{ink::SCENARIO_SOURCE, ink_json::InkJson},
+    dialog_system::{
+        dialog_log::DialogLog,
+        external_functions::{
+            InkCall, InkCallQueue, InkExternalFunctions, InkQueries, InkQuerySnapshot,
+        },
+        story_variables::StoryVariables,
+    },
     global::{GameState, loader::LoadingData},
     map::npc::{AvatarFilePath, NpcName},
+    player::Player,
 };
 
+pub mod dialog_log;
+pub mod external_functions;
+pub mod story_variables;
 mod utils;
 
 #[derive(Resource, Default)]
@@ -18,9 +34,57 @@ struct DialogsCache {
     dialogs: HashMap<String, String>,
 }
 
+impl DialogsCache {
+    fn insert(&mut self, path: &str, inkjson: &str, external_functions: &InkExternalFunctions) {
+        let unknown_functions = utils::unknown_external_functions(
+            &utils::external_function_names(inkjson),
+            external_functions,
+        );
+
+        if !unknown_functions.is_empty() {
+            error!(
+                "{}: unknown Ink external functions: {}",
+                path,
+                unknown_functions.join(", ")
+            );
+        }
+
+        self.dialogs.insert(path.into(), inkjson.into());
+    }
+}
+
+/// A story kept alive for the length of a conversation, so that its JSON
+/// is only parsed once instead of on every dialog step
+struct ActiveStory {
+    file_path: String,
+    story: Story,
+    call_queue: InkCallQueue,
+    query_snapshot: InkQuerySnapshot,
+    shared_variables: Vec<String>,
+    /// Id of the conversation in the [`DialogLog`]
+    conversation: u32,
+}
+
+/// Stories of the ongoing conversations, keyed by their source entity.
+/// `Story` is not `Send`, so this is a non send resource
+#[derive(Default)]
+struct ActiveStories(HashMap<Entity, ActiveStory>);
+
+/// Entities whose last dialog step failed, their conversation ends on the next step
+#[derive(Resource, Default)]
+struct FailedDialogs(HashSet<Entity>);
+
 #[derive(Component, Default)]
 pub struct DialogFilePath(pub String);
 
+/// Plain text shown in the dialog box by sources without an Ink story, like examined objects
+#[derive(Component, Default)]
+pub struct Narration(pub String);
+
+/// Narrations on screen, their next step ends the conversation
+#[derive(Resource, Default)]
+struct ShownNarrations(HashSet<Entity>);
+
 #[derive(Component, Default)]
 pub struct DialogState(pub String);
 
@@ -39,12 +103,35 @@ pub struct DialogChoice {
     pub body: String,
 }
 
+/// A line of dialog along with its Ink tags:
+/// - `# speaker: Gaidheal` changes who is talking until the next `speaker` tag
+/// - `# portrait: gaidheal_young` changes the speaker portrait until the next `portrait` tag
+/// - `# emotion: angry` uses the `_angry` variant of the portrait for this line only
+/// - `# sfx: door_creak` plays an object audio of the dialog source
+#[derive(Debug, Clone)]
+pub struct DialogLine {
+    pub text: String,
+    pub speaker: String,
+    pub image_path: String,
+    pub emotion: Option<String>,
+    pub sfx: Option<String>,
+    /// Tags without a known key
+    pub tags: Vec<String>,
+}
+
+impl DialogLine {
+    /// Portrait variant for the line emotion, if it has one
+    pub fn emotion_image_path(&self) -> Option<String> {
+        self.emotion
+            .as_ref()
+            .map(|emotion| utils::with_emotion(&self.image_path, emotion))
+    }
+}
+
 #[derive(Message)]
 pub struct DisplayCurrentDialogEvent {
     pub source_entity: Entity,
-    pub source_name: String,
-    pub image_path: String,
-    pub lines: Vec<String>,
+    pub lines: Vec<DialogLine>,
     pub choices: Vec<DialogChoice>,
 }
 
@@ -57,16 +144,72 @@ pub struct UpdateDialogStateEvent {
 #[derive(Message)]
 pub struct DialogEndedEvent;
 
+/// Written for each `# key: value` tag not handled by the dialog system,
+/// such as `# clue: torn_letter`
+#[derive(Message, Debug, Clone)]
+pub struct DialogTagEvent {
+    pub source_entity: Entity,
+    pub key: String,
+    pub value: String,
+}
+
+/// Possible errors that can be produced while running a dialog
+#[non_exhaustive]
+#[derive(Debug, Clone, Error)]
+pub enum DialogError {
+    #[error("Dialog file is not loaded")]
+    MissingFile,
+    #[error("Story can't be read: {0}")]
+    Unreadable(String),
+    #[error("Could not load story state: {0}")]
+    InvalidState(String),
+    #[error("Could not load story knot: {0}")]
+    MissingKnot(String),
+    #[error("Could not set story choice {index}: {cause}")]
+    InvalidChoice { index: usize, cause: String },
+    #[error("Unknown Ink external functions: {}", .0.join(", "))]
+    UnknownExternalFunctions(Vec<String>),
+    #[error("Could not bind Ink external functions: {0}")]
+    Binding(String),
+}
+
+/// Written when a dialog step fails, the conversation is then closed after a fallback line
+#[derive(Message, Debug, Clone)]
+pub struct DialogErrorEvent {
+    pub source_entity: Entity,
+    pub file_path: String,
+    pub knot: String,
+    pub cause: DialogError,
+}
+
 pub fn plugin(app: &mut App) {
     app.add_message::<RunDialogEvent>();
     app.add_message::<DisplayCurrentDialogEvent>();
     app.add_message::<DialogEndedEvent>();
     app.add_message::<UpdateDialogStateEvent>();
+    app.add_message::<DialogErrorEvent>();
+    app.add_message::<DialogTagEvent>();
     app.init_resource::<DialogsCache>();
+    app.init_resource::<FailedDialogs>();
+    app.init_resource::<ShownNarrations>();
+    app.init_non_send_resource::<ActiveStories>();
+    app.init_resource::<StoryVariables>();
+    app.init_resource::<DialogLog>();
+    app.init_resource::<InkExternalFunctions>();
+    app.init_resource::<InkQueries>();
     app.add_systems(OnEnter(GameState::InGame), load_dialog_folder);
     app.add_systems(
         Update,
-        (cache_dialogs, run_dialog, update_dialog_state)
+        (
+            cache_dialogs,
+            reload_dialogs,
+            validate_dialog_knots,
+            run_dialog,
+            run_narrations,
+            report_dialog_errors,
+            update_dialog_state,
+            drop_orphan_stories,
+        )
             .run_if(in_state(GameState::InGame))
             .chain(),
     );
@@ -77,7 +220,7 @@ fn load_dialog_folder(
     asset_server: ResMut<AssetServer>,
     mut loading_data: ResMut<LoadingData>,
 ) {
-    let folder = asset_server.load_folder("dialogs");
+    let folder = asset_server.load_folder(format!("{}://dialogs", SCENARIO_SOURCE));
 
     loading_data.loading_assets.push(folder.clone().into());
 
@@ -90,6 +233,7 @@ fn cache_dialogs(
     loaded_folders: Res<Assets<LoadedFolder>>,
     dialog_jsons: ResMut<Assets<InkJson>>,
     mut dialogs_cache: ResMut<DialogsCache>,
+    external_functions: Res<InkExternalFunctions>,
 ) {
     for event in events.read() {
         if event.is_loaded_with_dependencies(&dialogs_folder.0) {
@@ -101,20 +245,128 @@ fn cache_dialogs(
                 if let Some(dialog_json) = dialog_jsons.get(id)
                     && let Some(path) = handle.path().unwrap().path().to_str()
                 {
-                    dialogs_cache
-                        .dialogs
-                        .insert(path.into(), dialog_json.string.clone());
+                    dialogs_cache.insert(path, &dialog_json.string, &external_functions);
                 }
             }
         }
     }
 }
 
+/// Refreshes the cache when a dialog file changes on disk. Ongoing conversations
+/// of that file go on from their current state in the new story, or end on
+/// their next step when that state does not fit the new story anymore
+fn reload_dialogs(
+    mut events: MessageReader<AssetEvent<InkJson>>,
+    mut update_entity_event: MessageWriter<UpdateDialogStateEvent>,
+    dialog_jsons: Res<Assets<InkJson>>,
+    asset_server: Res<AssetServer>,
+    mut dialogs_cache: ResMut<DialogsCache>,
+    external_functions: Res<InkExternalFunctions>,
+    mut failed_dialogs: ResMut<FailedDialogs>,
+    mut active_stories: NonSendMut<ActiveStories>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+
+        let Some(dialog_json) = dialog_jsons.get(*id) else {
+            continue;
+        };
+
+        let Some(asset_path) = asset_server.get_path(*id) else {
+            continue;
+        };
+
+        let Some(path) = asset_path.path().to_str() else {
+            continue;
+        };
+
+        dialogs_cache.insert(path, &dialog_json.string, &external_functions);
+
+        info!("Dialog {} reloaded", path);
+
+        let reloaded_entities: Vec<Entity> = active_stories
+            .0
+            .iter()
+            .filter(|(_, active_story)| active_story.file_path == path)
+            .map(|(entity, _)| *entity)
+            .collect();
+
+        for entity in reloaded_entities {
+            let Some(mut active_story) = active_stories.0.remove(&entity) else {
+                continue;
+            };
+
+            let reloaded_story = active_story
+                .story
+                .save_state()
+                .map_err(|err| DialogError::InvalidState(format!("{:?}", err)))
+                .and_then(|state| {
+                    utils::get_story_with_state(
+                        &dialog_json.string,
+                        &state,
+                        "",
+                        &external_functions,
+                        &active_story.call_queue,
+                        &active_story.query_snapshot,
+                    )
+                });
+
+            match reloaded_story {
+                Ok(story) => {
+                    active_story.story = story;
+                    active_story.shared_variables =
+                        utils::shared_variable_names(&dialog_json.string);
+
+                    active_stories.0.insert(entity, active_story);
+                }
+                Err(err) => {
+                    warn!(
+                        "Conversation of {} ends as it can't go on in the reloaded story: {}",
+                        path, err
+                    );
+
+                    failed_dialogs.0.insert(entity);
+
+                    update_entity_event.write(UpdateDialogStateEvent {
+                        source_entity: entity,
+                        dialog_state: String::new(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Reports knots that do not exist in their dialog file, as soon as either of them changes
+fn validate_dialog_knots(
+    dialogs_cache: Res<DialogsCache>,
+    entities: Query<(Ref<DialogFilePath>, Ref<DialogKnot>)>,
+) {
+    for (file_path, dialog_knot) in entities {
+        if dialog_knot.0.is_empty()
+            || !(dialogs_cache.is_changed() || file_path.is_changed() || dialog_knot.is_changed())
+        {
+            continue;
+        }
+
+        if let Some(dialog_file) = dialogs_cache.dialogs.get(&file_path.0)
+            && !utils::has_knot(dialog_file, &dialog_knot.0)
+        {
+            error!("Dialog {} has no knot \"{}\"", file_path.0, dialog_knot.0);
+        }
+    }
+}
+
 fn run_dialog(
+    mut commands: Commands,
     mut dialog_event: MessageReader<RunDialogEvent>,
     mut dialog_ui_event: MessageWriter<DisplayCurrentDialogEvent>,
     mut update_entity_event: MessageWriter<UpdateDialogStateEvent>,
     mut dialog_ended_event: MessageWriter<DialogEndedEvent>,
+    mut dialog_error_event: MessageWriter<DialogErrorEvent>,
+    mut dialog_tag_event: MessageWriter<DialogTagEvent>,
     entities: Query<(
         &DialogFilePath,
         &DialogState,
@@ -122,48 +374,252 @@ fn run_dialog(
         &NpcName,
         &AvatarFilePath,
     )>,
+    players: Query<Entity, With<Player>>,
     dialogs_cache: Res<DialogsCache>,
+    external_functions: Res<InkExternalFunctions>,
+    ink_queries: Res<InkQueries>,
+    mut story_variables: ResMut<StoryVariables>,
+    mut failed_dialogs: ResMut<FailedDialogs>,
+    mut active_stories: NonSendMut<ActiveStories>,
+    mut dialog_log: ResMut<DialogLog>,
 ) {
     for event in dialog_event.read() {
-        if let Ok((file_path, dialog_state, dialog_knot, name, avatar_file_path)) =
+        if failed_dialogs.0.remove(&event.source_entity) {
+            dialog_ended_event.write(DialogEndedEvent);
+            continue;
+        }
+
+        let Ok((file_path, dialog_state, dialog_knot, name, avatar_file_path)) =
             entities.get(event.source_entity)
-            && let Some(dialog_file) = dialogs_cache.dialogs.get(&file_path.0)
-        {
-            let mut story =
-                utils::get_story_with_state(dialog_file, &dialog_state.0, &dialog_knot.0);
+        else {
+            continue;
+        };
+
+        let mut fail = |cause: DialogError| {
+            failed_dialogs.0.insert(event.source_entity);
+
+            dialog_error_event.write(DialogErrorEvent {
+                source_entity: event.source_entity,
+                file_path: file_path.0.clone(),
+                knot: dialog_knot.0.clone(),
+                cause,
+            });
+
+            dialog_ui_event.write(DisplayCurrentDialogEvent {
+                source_entity: event.source_entity,
+                lines: dialog_lines(vec![("...".into(), vec![])], &name.0, &avatar_file_path.0),
+                choices: vec![],
+            });
+        };
+
+        let mut active_story = match active_stories.0.remove(&event.source_entity) {
+            // The conversation goes on with its live story
+            Some(active_story) if active_story.file_path == file_path.0 => active_story,
+            _ => {
+                let Some(dialog_file) = dialogs_cache.dialogs.get(&file_path.0) else {
+                    fail(DialogError::MissingFile);
+                    continue;
+                };
+
+                let call_queue = InkCallQueue::default();
+                let query_snapshot = InkQuerySnapshot::default();
+
+                match utils::get_story_with_state(
+                    dialog_file,
+                    &dialog_state.0,
+                    &dialog_knot.0,
+                    &external_functions,
+                    &call_queue,
+                    &query_snapshot,
+                ) {
+                    Ok(story) => ActiveStory {
+                        file_path: file_path.0.clone(),
+                        story,
+                        call_queue,
+                        query_snapshot,
+                        shared_variables: utils::shared_variable_names(dialog_file),
+                        conversation: dialog_log.start_conversation(),
+                    },
+                    Err(err) => {
+                        // A broken state would fail every following conversation
+                        if let DialogError::InvalidState(_) = err {
+                            update_entity_event.write(UpdateDialogStateEvent {
+                                source_entity: event.source_entity,
+                                dialog_state: String::new(),
+                            });
+                        }
 
-            if let Some(choice_index) = event.choice_index {
-                story
-                    .choose_choice_index(choice_index)
-                    .expect("Could not set story choice");
+                        fail(err);
+                        continue;
+                    }
+                }
             }
+        };
+        let story = &mut active_story.story;
 
-            let lines = utils::get_lines(&mut story);
+        story_variables.push_into(story, &active_story.shared_variables);
+        active_story.query_snapshot.update(&ink_queries);
 
-            if let Ok(dialog_state) = story.save_state() {
-                update_entity_event.write(UpdateDialogStateEvent {
-                    source_entity: event.source_entity,
-                    dialog_state,
+        if let Some(choice_index) = event.choice_index {
+            let choice = utils::get_choices(story)
+                .into_iter()
+                .find(|choice| choice.index == choice_index);
+
+            if let Err(err) = story.choose_choice_index(choice_index) {
+                fail(DialogError::InvalidChoice {
+                    index: choice_index,
+                    cause: format!("{:?}", err),
                 });
+                continue;
             }
 
-            let choices = utils::get_choices(&story);
+            if let Some(choice) = choice {
+                dialog_log.record_choice(active_story.conversation, &choice.body);
+            }
+        }
+
+        let raw_lines = utils::get_lines(story);
+
+        story_variables.pull_from(story, &active_story.shared_variables);
 
-            if lines.is_empty() && choices.is_empty() {
-                dialog_ended_event.write(DialogEndedEvent);
-            } else {
-                dialog_ui_event.write(DisplayCurrentDialogEvent {
+        for (name, arguments) in active_story.call_queue.drain() {
+            external_functions.dispatch(
+                &InkCall {
+                    name,
+                    arguments,
                     source_entity: event.source_entity,
-                    source_name: name.0.clone(),
-                    image_path: avatar_file_path.0.clone(),
-                    lines,
-                    choices,
+                    player_entity: players.iter().next(),
+                },
+                &mut commands,
+            );
+        }
+
+        if let Ok(dialog_state) = story.save_state() {
+            update_entity_event.write(UpdateDialogStateEvent {
+                source_entity: event.source_entity,
+                dialog_state,
+            });
+        }
+
+        let choices = utils::get_choices(story);
+        let lines = dialog_lines(raw_lines, &name.0, &avatar_file_path.0);
+
+        for line in &lines {
+            dialog_log.record_line(active_story.conversation, &line.speaker, &line.text);
+
+            for (key, value) in line.tags.iter().filter_map(|tag| tag.split_once(':')) {
+                dialog_tag_event.write(DialogTagEvent {
+                    source_entity: event.source_entity,
+                    key: key.trim().into(),
+                    value: value.trim().into(),
                 });
             }
+        }
+
+        if lines.is_empty() && choices.is_empty() {
+            dialog_ended_event.write(DialogEndedEvent);
+        } else {
+            active_stories.0.insert(event.source_entity, active_story);
+
+            dialog_ui_event.write(DisplayCurrentDialogEvent {
+                source_entity: event.source_entity,
+                lines,
+                choices,
+            });
+        }
+    }
+}
+
+fn run_narrations(
+    mut dialog_event: MessageReader<RunDialogEvent>,
+    mut dialog_ui_event: MessageWriter<DisplayCurrentDialogEvent>,
+    mut dialog_ended_event: MessageWriter<DialogEndedEvent>,
+    narrations: Query<&Narration>,
+    mut shown_narrations: ResMut<ShownNarrations>,
+    mut dialog_log: ResMut<DialogLog>,
+) {
+    for event in dialog_event.read() {
+        let Ok(narration) = narrations.get(event.source_entity) else {
+            continue;
         };
+
+        if shown_narrations.0.remove(&event.source_entity) {
+            dialog_ended_event.write(DialogEndedEvent);
+            continue;
+        }
+
+        let conversation = dialog_log.start_conversation();
+        dialog_log.record_line(conversation, "", &narration.0);
+
+        shown_narrations.0.insert(event.source_entity);
+
+        dialog_ui_event.write(DisplayCurrentDialogEvent {
+            source_entity: event.source_entity,
+            lines: dialog_lines(vec![(narration.0.clone(), vec![])], "", ""),
+            choices: vec![],
+        });
     }
 }
 
+/// Drops the stories of entities despawned mid conversation, e.g. on level change
+fn drop_orphan_stories(
+    mut active_stories: NonSendMut<ActiveStories>,
+    entities: Query<(), With<DialogFilePath>>,
+) {
+    if !active_stories.0.is_empty() {
+        active_stories
+            .0
+            .retain(|entity, _| entities.contains(*entity));
+    }
+}
+
+fn report_dialog_errors(mut dialog_error_event: MessageReader<DialogErrorEvent>) {
+    for event in dialog_error_event.read() {
+        error!(
+            "Dialog {} of {} failed at knot \"{}\": {}",
+            event.file_path, event.source_entity, event.knot, event.cause
+        );
+    }
+}
+
+fn dialog_lines(
+    raw_lines: Vec<(String, Vec<String>)>,
+    source_name: &str,
+    avatar_file_path: &str,
+) -> Vec<DialogLine> {
+    let mut speaker = source_name.to_string();
+    let mut portrait = avatar_file_path.to_string();
+
+    raw_lines
+        .into_iter()
+        .map(|(text, tags)| {
+            let line_tags = utils::parse_line_tags(&tags);
+
+            if let Some(new_speaker) = line_tags.speaker {
+                portrait = if new_speaker == source_name {
+                    avatar_file_path.into()
+                } else {
+                    utils::portrait_path(&new_speaker)
+                };
+                speaker = new_speaker;
+            }
+
+            if let Some(new_portrait) = line_tags.portrait {
+                portrait = utils::portrait_path(&new_portrait);
+            }
+
+            DialogLine {
+                text,
+                speaker: speaker.clone(),
+                image_path: portrait.clone(),
+                emotion: line_tags.emotion,
+                sfx: line_tags.sfx,
+                tags: line_tags.others,
+            }
+        })
+        .collect()
+}
+
 fn update_dialog_state(
     mut entities: Query<&mut DialogState>,
     mut update_event: MessageReader<UpdateDialogStateEvent>,
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bladeink::{story::Story, value_type::ValueType};
+use serde::{Deserialize, Serialize};
+
+/// Ink `VAR`s starting with this prefix are shared between every story and the game.
+/// `VAR shared_met_fishermen = false` is read and written as `met_fishermen`
+pub const SHARED_VARIABLE_PREFIX: &str = "shared_";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StoryValue {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl From<bool> for StoryValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for StoryValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f32> for StoryValue {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for StoryValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for StoryValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl StoryValue {
+    pub fn to_ink(&self) -> ValueType {
+        match self {
+            Self::Bool(value) => ValueType::Bool(*value),
+            Self::Int(value) => ValueType::Int(*value),
+            Self::Float(value) => ValueType::Float(*value),
+            Self::String(value) => ValueType::new(value.as_str()),
+        }
+    }
+
+    pub fn from_ink(value: &ValueType) -> Option<Self> {
+        match value {
+            ValueType::Bool(value) => Some(Self::Bool(*value)),
+            ValueType::Int(value) => Some(Self::Int(*value)),
+            ValueType::Float(value) => Some(Self::Float(*value)),
+            ValueType::String(_) => value.get::<&str>().map(Self::from),
+            _ => None,
+        }
+    }
+}
+
+/// Story variables shared between every Ink story and the game systems
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoryVariables {
+    values: HashMap<String, StoryValue>,
+}
+
+impl StoryVariables {
+    pub fn get(&self, name: &str) -> Option<&StoryValue> {
+        self.values.get(name)
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name) {
+            Some(StoryValue::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<StoryValue>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Overrides the shared variables declared by the story with the game values
+    pub fn push_into(&self, story: &mut Story, shared_variables: &[String]) {
+        for ink_name in shared_variables {
+            if let Some(value) = self.get(game_name(ink_name))
+                && let Err(err) = story.set_variable(ink_name, &value.to_ink())
+            {
+                warn!("Could not set story variable {}: {:?}", ink_name, err);
+            }
+        }
+    }
+
+    /// Reads back the shared variables declared by the story
+    pub fn pull_from(&mut self, story: &Story, shared_variables: &[String]) {
+        for ink_name in shared_variables {
+            if let Some(value) = story
+                .get_variable(ink_name)
+                .as_ref()
+                .and_then(StoryValue::from_ink)
+            {
+                self.set(game_name(ink_name), value);
+            }
+        }
+    }
+}
+
+fn game_name(ink_name: &str) -> &str {
+    ink_name
+        .strip_prefix(SHARED_VARIABLE_PREFIX)
+        .unwrap_or(ink_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::dialog_system::utils::shared_variable_names;
+
+    /// Compiled from `VAR shared_met_fishermen = false` and `VAR mood = "calm"`
+    const STORY: &str = r##"{"inkVersion":21,"root":[["^Hello.","\n","end",["done",{"#f":5,"#n":"g-0"}],null],"done",{"global decl":["ev",false,{"VAR=":"shared_met_fishermen"},"str","^calm","/str",{"VAR=":"mood"},"/ev","end",null],"#f":1}],"listDefs":{}}"##;
+
+    #[test]
+    fn moves_shared_variables_between_stories() {
+        // Setup
+        let shared_variables = shared_variable_names(STORY);
+        let mut story_variables = StoryVariables::default();
+        let mut first_story = Story::new(STORY).unwrap();
+        let mut second_story = Story::new(STORY).unwrap();
+        first_story
+            .set_variable("shared_met_fishermen", &ValueType::Bool(true))
+            .unwrap();
+        first_story
+            .set_variable("mood", &ValueType::new("angry"))
+            .unwrap();
+
+        // Run
+        story_variables.pull_from(&first_story, &shared_variables);
+        story_variables.push_into(&mut second_story, &shared_variables);
+
+        // Check
+        assert_eq!(shared_variables, vec!["shared_met_fishermen".to_string()]);
+        assert_eq!(
+            story_variables.get("met_fishermen"),
+            Some(&StoryValue::Bool(true))
+        );
+        assert_eq!(story_variables.get("mood"), None);
+        assert_eq!(
+            second_story
+                .get_variable("shared_met_fishermen")
+                .as_ref()
+                .and_then(StoryValue::from_ink),
+            Some(StoryValue::Bool(true))
+        );
+        assert_eq!(
+            second_story
+                .get_variable("mood")
+                .as_ref()
+                .and_then(StoryValue::from_ink),
+            Some(StoryValue::from("calm"))
+        );
+    }
+}
@@ -1,40 +1,116 @@
 use std::rc::Rc;
 
 use bladeink::story::Story;
+use serde_json::Value;
 
-use crate::game::dialog_system::DialogChoice;
+use crate::game::dialog_system::{
+    DialogChoice, DialogError,
+    external_functions::{InkCallQueue, InkExternalFunctions, InkQuerySnapshot},
+    story_variables::SHARED_VARIABLE_PREFIX,
+};
 
-pub fn get_story_with_state(inkjson: &str, state: &str, knot: &str) -> Story {
-    let mut story = match Story::new(inkjson) {
-        Ok(story) => story,
-        Err(err) => panic!("Story can't be read: {:?}", err),
-    };
+pub fn get_story_with_state(
+    inkjson: &str,
+    state: &str,
+    knot: &str,
+    external_functions: &InkExternalFunctions,
+    call_queue: &InkCallQueue,
+    query_snapshot: &InkQuerySnapshot,
+) -> Result<Story, DialogError> {
+    let function_names = external_function_names(inkjson);
+    let unknown_functions = unknown_external_functions(&function_names, external_functions);
+
+    if !unknown_functions.is_empty() {
+        return Err(DialogError::UnknownExternalFunctions(unknown_functions));
+    }
+
+    let mut story =
+        Story::new(inkjson).map_err(|err| DialogError::Unreadable(format!("{:?}", err)))?;
+
+    external_functions
+        .bind(&mut story, &function_names, call_queue, query_snapshot)
+        .map_err(DialogError::Binding)?;
 
     if !state.is_empty() {
-        story.load_state(state).expect("Could not load story state");
+        story
+            .load_state(state)
+            .map_err(|err| DialogError::InvalidState(format!("{:?}", err)))?;
     }
 
     if !knot.is_empty() {
         story
             .choose_path_string(knot, true, None)
-            .expect("Could not load story knot");
+            .map_err(|err| DialogError::MissingKnot(format!("{:?}", err)))?;
     }
 
-    story
+    Ok(story)
 }
 
-pub fn get_lines(story: &mut Story) -> Vec<String> {
+pub fn get_lines(story: &mut Story) -> Vec<(String, Vec<String>)> {
     let mut lines = Vec::new();
 
     while story.can_continue() {
         if let Ok(line) = story.cont() {
-            lines.push(line);
+            let tags = story.get_current_tags().unwrap_or_default();
+
+            lines.push((line, tags));
         }
     }
 
     lines
 }
 
+/// Tags of a single line, written as `# key: value` in Ink
+#[derive(Default, Debug, PartialEq)]
+pub struct LineTags {
+    pub speaker: Option<String>,
+    pub portrait: Option<String>,
+    pub emotion: Option<String>,
+    pub sfx: Option<String>,
+    /// Tags without a known key, kept untouched
+    pub others: Vec<String>,
+}
+
+pub fn parse_line_tags(tags: &[String]) -> LineTags {
+    let mut line_tags = LineTags::default();
+
+    for tag in tags {
+        let Some((key, value)) = tag.split_once(':') else {
+            line_tags.others.push(tag.trim().into());
+            continue;
+        };
+
+        let value = Some(value.trim().to_string());
+
+        match key.trim() {
+            "speaker" => line_tags.speaker = value,
+            "portrait" => line_tags.portrait = value,
+            "emotion" => line_tags.emotion = value,
+            "sfx" => line_tags.sfx = value,
+            _ => line_tags.others.push(tag.trim().into()),
+        }
+    }
+
+    line_tags
+}
+
+/// Portraits of speakers without an NPC avatar are looked up by name,
+/// `Old Gaidheal` being `textures/portraits/old_gaidheal.png`
+pub fn portrait_path(portrait: &str) -> String {
+    format!(
+        "textures/portraits/{}.png",
+        portrait.trim().to_lowercase().replace(' ', "_")
+    )
+}
+
+/// `textures/npcs/avatar.png` with the `angry` emotion is `textures/npcs/avatar_angry.png`
+pub fn with_emotion(image_path: &str, emotion: &str) -> String {
+    match image_path.rsplit_once('.') {
+        Some((path, extension)) => format!("{}_{}.{}", path, emotion, extension),
+        None => format!("{}_{}", image_path, emotion),
+    }
+}
+
 pub fn get_choices(story: &Story) -> Vec<DialogChoice> {
     let mut choices = vec![];
 
@@ -49,3 +125,210 @@ pub fn get_choices(story: &Story) -> Vec<DialogChoice> {
 
     choices
 }
+
+/// Lists the shared `VAR`s declared in a compiled story
+pub fn shared_variable_names(inkjson: &str) -> Vec<String> {
+    let Ok(json) = serde_json::from_str::<Value>(inkjson) else {
+        return vec![];
+    };
+
+    // Global declarations live in the named content of the root container
+    json["root"]
+        .as_array()
+        .and_then(|root| root.last())
+        .map(|named_content| &named_content["global decl"])
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|instruction| instruction["VAR="].as_str())
+        .filter(|name| name.starts_with(SHARED_VARIABLE_PREFIX))
+        .map(String::from)
+        .collect()
+}
+
+/// Whether a `knot` or `knot.stitch` path exists in a compiled story
+pub fn has_knot(inkjson: &str, knot: &str) -> bool {
+    let Ok(json) = serde_json::from_str::<Value>(inkjson) else {
+        return false;
+    };
+
+    let mut container = &json["root"];
+
+    // Named sub containers live in the last element of their parent container
+    for segment in knot.split('.') {
+        match container
+            .as_array()
+            .and_then(|content| content.last())
+            .and_then(|named_content| named_content.get(segment))
+        {
+            Some(named_container) => container = named_container,
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// Lists the `EXTERNAL` functions called by a compiled story
+pub fn external_function_names(inkjson: &str) -> Vec<String> {
+    fn collect(value: &Value, names: &mut Vec<String>) {
+        match value {
+            Value::Array(values) => values.iter().for_each(|value| collect(value, names)),
+            Value::Object(object) => {
+                if let Some(name) = object.get("x()").and_then(Value::as_str)
+                    && !names.iter().any(|known| known == name)
+                {
+                    names.push(name.into());
+                }
+
+                object.values().for_each(|value| collect(value, names));
+            }
+            _ => (),
+        }
+    }
+
+    let mut names = vec![];
+
+    if let Ok(json) = serde_json::from_str::<Value>(inkjson) {
+        collect(&json["root"], &mut names);
+    }
+
+    names
+}
+
+pub fn unknown_external_functions(
+    function_names: &[String],
+    external_functions: &InkExternalFunctions,
+) -> Vec<String> {
+    function_names
+        .iter()
+        .filter(|name| !external_functions.contains(name))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use test::Bencher;
+
+    use super::*;
+
+    /// Story used by the benchmarks, compiled beforehand so that they do not need `inklecate`.
+    /// Override it with `DIALOG_BENCH_FILE` to measure a large script:
+    /// `DIALOG_BENCH_FILE=path/to/story.ink.json cargo bench`
+    fn bench_story() -> String {
+        let path = env::var("DIALOG_BENCH_FILE")
+            .unwrap_or_else(|_| "src/game/dialog_system/bench_story.ink.json".into());
+
+        fs::read_to_string(path).expect("Could not read the benchmarked story")
+    }
+
+    #[test]
+    fn parses_line_tags() {
+        // Setup
+        let tags = vec![
+            "speaker: Gaidheal".to_string(),
+            " emotion:angry ".to_string(),
+            "sfx: door_creak".to_string(),
+            "shake".to_string(),
+        ];
+
+        // Run
+        let line_tags = parse_line_tags(&tags);
+
+        // Check
+        assert_eq!(
+            line_tags,
+            LineTags {
+                speaker: Some("Gaidheal".into()),
+                portrait: None,
+                emotion: Some("angry".into()),
+                sfx: Some("door_creak".into()),
+                others: vec!["shake".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn adds_emotion_to_image_path() {
+        assert_eq!(
+            with_emotion("textures/npcs/dummy_npc_avatar.png", "angry"),
+            "textures/npcs/dummy_npc_avatar_angry.png"
+        );
+        assert_eq!(
+            with_emotion(&portrait_path("Old Gaidheal"), "sad"),
+            "textures/portraits/old_gaidheal_sad.png"
+        );
+    }
+
+    /// State of the benchmarked story waiting on its first choice, taken outside of the timings
+    fn bench_state(inkjson: &str, external_functions: &InkExternalFunctions) -> String {
+        let mut story = get_story_with_state(
+            inkjson,
+            "",
+            "",
+            external_functions,
+            &InkCallQueue::default(),
+            &InkQuerySnapshot::default(),
+        )
+        .unwrap();
+
+        get_lines(&mut story);
+
+        story.save_state().unwrap()
+    }
+
+    /// Dialog step cost when the story is parsed again from its JSON and state
+    #[bench]
+    fn step_with_reparsed_story(bencher: &mut Bencher) {
+        let inkjson = bench_story();
+        let external_functions = InkExternalFunctions::default();
+        let call_queue = InkCallQueue::default();
+        let query_snapshot = InkQuerySnapshot::default();
+        let state = bench_state(&inkjson, &external_functions);
+
+        bencher.iter(|| {
+            let mut story = get_story_with_state(
+                &inkjson,
+                &state,
+                "",
+                &external_functions,
+                &call_queue,
+                &query_snapshot,
+            )
+            .unwrap();
+
+            story.choose_choice_index(0).unwrap();
+            story.continue_maximally().unwrap()
+        });
+    }
+
+    /// Dialog step cost when the story stays live during the conversation,
+    /// only its state is restored between steps
+    #[bench]
+    fn step_with_live_story(bencher: &mut Bencher) {
+        let inkjson = bench_story();
+        let external_functions = InkExternalFunctions::default();
+        let call_queue = InkCallQueue::default();
+        let query_snapshot = InkQuerySnapshot::default();
+        let state = bench_state(&inkjson, &external_functions);
+        let mut story = get_story_with_state(
+            &inkjson,
+            "",
+            "",
+            &external_functions,
+            &call_queue,
+            &query_snapshot,
+        )
+        .unwrap();
+
+        bencher.iter(|| {
+            story.load_state(&state).unwrap();
+
+            story.choose_choice_index(0).unwrap();
+            story.continue_maximally().unwrap()
+        });
+    }
+}
@@ -0,0 +1,377 @@
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::AseSlice;
+use bevy_ecs_ldtk::{EntityInstance, GridCoords};
+
+use crate::game::{
+    combat::{Fighting, Health},
+    global::{GameState, despawn_entity_on_level_change},
+    map::{
+        GRID_SIZE, InstanceIid, npc::update_npc_position, utils, world_state::WorldState,
+        zones::ZoneOccupant,
+    },
+    physics::{
+        colliders::{Collider, CollisionLayer, Intangible, LevelColliders},
+        pathfinding::PathFollower,
+    },
+    player::{Footstep, Player, player_roaming},
+    tick::{GameTempo, MainTick, MainTickCounter},
+};
+
+const IDENTIFIER: &str = "Enemy";
+const FIELDS: [&str; 4] = ["SightRange", "HearingRange", "Aggressive", "Health"];
+const DEFAULT_SIGHT_RANGE: i32 = 5;
+const DEFAULT_HEALTH: i32 = 3;
+const DEFAULT_HEARING_RANGE: i32 = 3;
+/// Tiles an enemy patrols around its spawn
+const PATROL_RANGE: i32 = 3;
+const ENEMY_Z_DEPTH: f32 = 2.;
+// Placeholder sprite, a tinted NPC, until enemies get their own aseprite
+const ENEMY_ASEPRITE: &str = "textures/npcs/dummy_npc.aseprite";
+const ENEMY_SLICE: &str = "player_idle";
+const ENEMY_TINT: Color = Color::srgb(1., 0.4, 0.4);
+/// World state flag of the enemies already defeated
+pub const DEFEATED_FLAG: &str = "defeated";
+
+#[derive(Component, Clone, Debug, Default)]
+#[require(ZoneOccupant)]
+pub struct Enemy {
+    /// In tiles, walls block the sight
+    pub sight_range: i32,
+    /// In tiles, player footsteps are heard through walls
+    pub hearing_range: i32,
+    /// Chases the player on sight, flees otherwise
+    pub aggressive: bool,
+    /// Tile the enemy spawned on, the center of its patrol
+    pub home: GridCoords,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyState {
+    Idle,
+    Patrol,
+    /// Walks to where the player was last seen or heard
+    Investigate(GridCoords),
+    /// Follows the player, remembering where it was last seen
+    Chase(GridCoords),
+    Flee,
+}
+
+/// What the enemy noticed during the last tick
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Perception {
+    pub player: Option<GridCoords>,
+    pub heard: Option<GridCoords>,
+}
+
+/// The enemy caught up with the player
+#[derive(Message)]
+pub struct EnemyEngaged {
+    pub enemy: Entity,
+}
+
+impl EnemyState {
+    /// Reacts to what the enemy noticed, `arrived` telling whether it reached its destination
+    pub fn next(&self, enemy: &Enemy, perception: &Perception, arrived: bool) -> Self {
+        if let Some(player) = perception.player {
+            return if enemy.aggressive {
+                Self::Chase(player)
+            } else {
+                Self::Flee
+            };
+        }
+
+        if let Some(heard) = perception.heard {
+            return Self::Investigate(heard);
+        }
+
+        match self {
+            Self::Idle => Self::Patrol,
+            Self::Patrol | Self::Investigate(_) if arrived => Self::Idle,
+            // Lost sight of the player
+            Self::Chase(last_seen) => Self::Investigate(*last_seen),
+            Self::Flee => Self::Idle,
+            state => *state,
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_message::<EnemyEngaged>();
+    app.add_systems(
+        Update,
+        (
+            despawn_entity_on_level_change::<Enemy>,
+            spawn_enemies,
+            // Enemies only engage a player walking around, not one talking or reading
+            (perceive_and_decide, engage.run_if(player_roaming)).chain(),
+            update_npc_position::<Enemy>,
+        )
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn spawn_enemies(
+    mut commands: Commands,
+    new_entity_instances: Query<&EntityInstance, Added<EntityInstance>>,
+    world_state: Res<WorldState>,
+    server: Res<AssetServer>,
+) {
+    for entity_instance in new_entity_instances {
+        if entity_instance.identifier != IDENTIFIER {
+            continue;
+        }
+
+        let defeated = world_state
+            .entities
+            .get(&entity_instance.iid)
+            .and_then(|snapshot| snapshot.flags.as_ref())
+            .is_some_and(|flags| flags.get(DEFEATED_FLAG) == Some(&true));
+
+        if defeated {
+            continue;
+        }
+
+        let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
+        let home: GridCoords = entity_instance.grid.into();
+
+        commands.spawn((
+            AseSlice {
+                name: ENEMY_SLICE.into(),
+                aseprite: server.load(ENEMY_ASEPRITE),
+            },
+            Sprite {
+                color: ENEMY_TINT,
+                ..Default::default()
+            },
+            InstanceIid(entity_instance.iid.clone()),
+            home,
+            Collider::default(),
+            CollisionLayer::ENEMY,
+            PathFollower::new(home),
+            EnemyState::Idle,
+            Health::new(*fields.integers.get(FIELDS[3]).unwrap_or(&DEFAULT_HEALTH)),
+            Enemy {
+                sight_range: *fields
+                    .integers
+                    .get(FIELDS[0])
+                    .unwrap_or(&DEFAULT_SIGHT_RANGE),
+                hearing_range: *fields
+                    .integers
+                    .get(FIELDS[1])
+                    .unwrap_or(&DEFAULT_HEARING_RANGE),
+                aggressive: *fields.bools.get(FIELDS[2]).unwrap_or(&true),
+                home,
+            },
+            Transform {
+                translation: bevy_ecs_ldtk::utils::grid_coords_to_translation(
+                    home,
+                    IVec2::splat(GRID_SIZE),
+                )
+                .extend(ENEMY_Z_DEPTH),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+fn distance(from: &GridCoords, to: &GridCoords) -> i32 {
+    (from.x - to.x).abs() + (from.y - to.y).abs()
+}
+
+/// Bresenham line between the two tiles, without them, `blocks` telling which tiles hide what is behind
+pub fn line_of_sight(
+    from: &GridCoords,
+    to: &GridCoords,
+    blocks: impl Fn(&GridCoords) -> bool,
+) -> bool {
+    let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+    let (step_x, step_y) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut error = dx + dy;
+    let mut current = *from;
+
+    loop {
+        if current == *to {
+            return true;
+        }
+
+        if current != *from && blocks(&current) {
+            return false;
+        }
+
+        let doubled_error = 2 * error;
+
+        if doubled_error >= dy {
+            error += dy;
+            current.x += step_x;
+        }
+
+        if doubled_error <= dx {
+            error += dx;
+            current.y += step_y;
+        }
+    }
+}
+
+fn perceive_and_decide(
+    mut footsteps: MessageReader<Footstep>,
+    mut heard_steps: Local<Vec<GridCoords>>,
+    main_tick: Res<MainTick>,
+    main_tick_counter: Res<MainTickCounter>,
+    game_tempo: Res<GameTempo>,
+    level_colliders: Res<LevelColliders>,
+    players: Query<&GridCoords, With<Player>>,
+    enemies: Query<
+        (&GridCoords, &Enemy, &mut EnemyState, &mut PathFollower),
+        // Hallucinations out of the current variant neither see nor hear
+        (Without<Fighting>, Without<Intangible>),
+    >,
+) {
+    // Footsteps are kept until the next tick
+    heard_steps.extend(footsteps.read().map(|step| step.grid_coords));
+
+    if !main_tick.timer.just_finished() {
+        return;
+    }
+
+    let heard_steps = std::mem::take(&mut *heard_steps);
+
+    for (grid_coords, enemy, mut state, mut follower) in enemies {
+        let player = players.iter().find(|player| {
+            distance(grid_coords, player) <= enemy.sight_range
+                // Projectiles fly where eyes see
+                && line_of_sight(grid_coords, player, |tile| {
+                    level_colliders.in_collider(tile, CollisionLayer::PROJECTILE)
+                })
+        });
+
+        let perception = Perception {
+            player: player.copied(),
+            heard: heard_steps
+                .iter()
+                .rev()
+                .find(|step| distance(grid_coords, step) <= enemy.hearing_range)
+                .copied(),
+        };
+
+        let next_state = match state.next(enemy, &perception, follower.arrived(*grid_coords)) {
+            // Patrols start on a measure
+            EnemyState::Patrol
+                if *state == EnemyState::Idle && !main_tick_counter.on_measure(&game_tempo) =>
+            {
+                EnemyState::Idle
+            }
+            next_state => next_state,
+        };
+
+        state.set_if_neq(next_state);
+
+        match next_state {
+            EnemyState::Idle => follower.go_to(*grid_coords),
+            EnemyState::Patrol if follower.arrived(*grid_coords) => {
+                let offset = IVec2::new(
+                    rand::random_range(-PATROL_RANGE..=PATROL_RANGE),
+                    rand::random_range(-PATROL_RANGE..=PATROL_RANGE),
+                );
+
+                follower.go_to(enemy.home + offset.into());
+            }
+            EnemyState::Patrol => (),
+            EnemyState::Investigate(target) => follower.go_to(target),
+            EnemyState::Chase(player) => follower.go_next_to(player),
+            EnemyState::Flee => {
+                if let Some(player) = perception.player {
+                    let away = IVec2::new(grid_coords.x - player.x, grid_coords.y - player.y)
+                        .signum()
+                        * enemy.sight_range;
+
+                    follower.go_to(*grid_coords + away.into());
+                }
+            }
+        }
+    }
+}
+
+fn engage(
+    mut engaged: MessageWriter<EnemyEngaged>,
+    // Also engages enemies that reached the player while they were busy
+    enemies: Query<(Entity, &GridCoords, &EnemyState), (Without<Fighting>, Without<Intangible>)>,
+    players: Query<&GridCoords, With<Player>>,
+) {
+    for (entity, grid_coords, state) in enemies {
+        if matches!(state, EnemyState::Chase(_))
+            && players
+                .iter()
+                .any(|player| distance(grid_coords, player) == 1)
+        {
+            engaged.write(EnemyEngaged { enemy: entity });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enemy(aggressive: bool) -> Enemy {
+        Enemy {
+            sight_range: DEFAULT_SIGHT_RANGE,
+            hearing_range: DEFAULT_HEARING_RANGE,
+            aggressive,
+            home: GridCoords { x: 0, y: 0 },
+        }
+    }
+
+    #[test]
+    fn walls_block_the_sight() {
+        // Setup
+        let wall = GridCoords { x: 2, y: 0 };
+        let blocks = |tile: &GridCoords| *tile == wall;
+
+        // Check
+        assert!(!line_of_sight(
+            &GridCoords { x: 0, y: 0 },
+            &GridCoords { x: 4, y: 0 },
+            blocks
+        ));
+        assert!(line_of_sight(
+            &GridCoords { x: 0, y: 1 },
+            &GridCoords { x: 4, y: 1 },
+            blocks
+        ));
+    }
+
+    #[test]
+    fn reacts_to_the_player() {
+        // Setup
+        let seen = Perception {
+            player: Some(GridCoords { x: 3, y: 0 }),
+            heard: None,
+        };
+        let heard = Perception {
+            player: None,
+            heard: Some(GridCoords { x: 1, y: 1 }),
+        };
+
+        // Check
+        assert_eq!(
+            EnemyState::Patrol.next(&enemy(true), &seen, false),
+            EnemyState::Chase(GridCoords { x: 3, y: 0 })
+        );
+        assert_eq!(
+            EnemyState::Patrol.next(&enemy(false), &seen, false),
+            EnemyState::Flee
+        );
+        assert_eq!(
+            EnemyState::Idle.next(&enemy(true), &heard, false),
+            EnemyState::Investigate(GridCoords { x: 1, y: 1 })
+        );
+        assert_eq!(
+            EnemyState::Chase(GridCoords { x: 3, y: 0 }).next(
+                &enemy(true),
+                &Perception::default(),
+                false
+            ),
+            EnemyState::Investigate(GridCoords { x: 3, y: 0 })
+        );
+    }
+}
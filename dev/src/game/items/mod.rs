@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+use bevy_ecs_ldtk::{EntityInstance, GridCoords};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    custom_asset_types::ron_asset::{RonAsset, ron_asset_plugin},
+    dialog_system::external_functions::{InkExternalFunctions, InkQueries},
+    global::{GameState, despawn_entity_on_level_change, loader::LoadingData},
+    madness::ChangeMadness,
+    map::{GRID_SIZE, InstanceIid, utils, world_state::WorldState},
+    physics::colliders::Intangible,
+    player::Player,
+};
+
+const ITEMS_FILE_PATH: &str = "data/inventory.items.ron";
+const IDENTIFIER: &str = "Item";
+const FIELDS: [&str; 1] = ["Id"];
+/// World state flag of the pickups already taken
+const PICKED_UP_FLAG: &str = "picked_up";
+/// Ink query telling whether the player carries an item: `{has_item("old_key"): ...}`
+const HAS_ITEM: &str = "has_item";
+const PICKUP_Z_DEPTH: f32 = 1.5;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemDefinition {
+    pub name: String,
+    pub description: String,
+    /// Madness gained, or lost when negative, on use
+    #[serde(default)]
+    pub madness: Option<f32>,
+    /// Removed from the inventory once used
+    #[serde(default)]
+    pub consumable: bool,
+}
+
+/// Every item of the game, keyed by id
+#[derive(Asset, TypePath, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct ItemDefinitions(pub HashMap<String, ItemDefinition>);
+
+impl RonAsset for ItemDefinitions {
+    const EXTENSIONS: &'static [&'static str] = &["items.ron"];
+}
+
+#[derive(Resource, Default)]
+pub struct ItemDefinitionsHandle(pub Handle<ItemDefinitions>);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InventoryItem {
+    pub id: String,
+    pub count: u32,
+}
+
+/// Items carried by the player, in the order they were found
+#[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    pub items: Vec<InventoryItem>,
+}
+
+impl Inventory {
+    pub fn has_item(&self, id: &str) -> bool {
+        self.items.iter().any(|item| item.id == id)
+    }
+
+    pub fn add(&mut self, id: &str) {
+        match self.items.iter_mut().find(|item| item.id == id) {
+            Some(item) => item.count += 1,
+            None => self.items.push(InventoryItem {
+                id: id.into(),
+                count: 1,
+            }),
+        }
+    }
+
+    /// Returns false when the item isn't carried
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(index) = self.items.iter().position(|item| item.id == id) else {
+            return false;
+        };
+
+        self.items[index].count -= 1;
+
+        if self.items[index].count == 0 {
+            self.items.remove(index);
+        }
+
+        true
+    }
+}
+
+/// Item lying on the map, picked up by walking over it
+#[derive(Component, Clone, Debug)]
+pub struct ItemPickup {
+    pub id: String,
+}
+
+#[derive(Message)]
+pub struct ItemPickedUp {
+    pub id: String,
+}
+
+/// Asks to use an item of the inventory
+#[derive(Message)]
+pub struct UseItem {
+    pub id: String,
+}
+
+#[derive(Message)]
+pub struct ItemUsed {
+    pub id: String,
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(ron_asset_plugin::<ItemDefinitions>);
+    app.add_message::<ItemPickedUp>();
+    app.add_message::<UseItem>();
+    app.add_message::<ItemUsed>();
+    app.add_systems(Startup, register_ink_functions);
+    app.add_systems(OnEnter(GameState::InGame), load_item_definitions);
+    app.add_systems(
+        Update,
+        (
+            add_inventory,
+            despawn_entity_on_level_change::<ItemPickup>,
+            spawn_pickups,
+            pick_up_items,
+            use_items,
+            update_ink_queries,
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn register_ink_functions(mut external_functions: ResMut<InkExternalFunctions>) {
+    external_functions.register_query(HAS_ITEM);
+}
+
+fn load_item_definitions(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_data: ResMut<LoadingData>,
+) {
+    let handle = asset_server.load(ITEMS_FILE_PATH);
+
+    loading_data.loading_assets.push(handle.clone().into());
+
+    commands.insert_resource(ItemDefinitionsHandle(handle));
+}
+
+fn add_inventory(
+    mut commands: Commands,
+    players: Query<Entity, (Added<Player>, Without<Inventory>)>,
+) {
+    for player in players {
+        commands.entity(player).insert(Inventory::default());
+    }
+}
+
+fn spawn_pickups(
+    mut commands: Commands,
+    new_entity_instances: Query<&EntityInstance, Added<EntityInstance>>,
+    world_state: Res<WorldState>,
+) {
+    for entity_instance in new_entity_instances {
+        if entity_instance.identifier != IDENTIFIER {
+            continue;
+        }
+
+        let picked_up = world_state
+            .entities
+            .get(&entity_instance.iid)
+            .and_then(|snapshot| snapshot.flags.as_ref())
+            .is_some_and(|flags| flags.get(PICKED_UP_FLAG) == Some(&true));
+
+        if picked_up {
+            continue;
+        }
+
+        let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
+
+        let Some(id) = fields.strings.get(FIELDS[0]) else {
+            warn!("Item {} has no id", entity_instance.iid);
+            continue;
+        };
+
+        commands.spawn((
+            // Placeholder until items get their own sprites
+            Sprite::from_color(GOLD, Vec2::splat(GRID_SIZE as f32 / 2.)),
+            InstanceIid(entity_instance.iid.clone()),
+            GridCoords {
+                ..entity_instance.grid.into()
+            },
+            Transform {
+                translation: bevy_ecs_ldtk::utils::grid_coords_to_translation(
+                    entity_instance.grid.into(),
+                    IVec2::splat(GRID_SIZE),
+                )
+                .extend(PICKUP_Z_DEPTH),
+                ..Default::default()
+            },
+            ItemPickup { id: id.clone() },
+            Name::new(format!("Item pickup ({id})")),
+        ));
+    }
+}
+
+fn pick_up_items(
+    mut commands: Commands,
+    mut item_picked_up: MessageWriter<ItemPickedUp>,
+    mut world_state: ResMut<WorldState>,
+    players: Query<(&GridCoords, &mut Inventory), (With<Player>, Changed<GridCoords>)>,
+    // Hallucinated pickups are only taken in their variants
+    pickups: Query<(Entity, &GridCoords, &InstanceIid, &ItemPickup), Without<Intangible>>,
+) {
+    for (player_grid_coords, mut inventory) in players {
+        for (entity, grid_coords, iid, pickup) in pickups {
+            if grid_coords != player_grid_coords {
+                continue;
+            }
+
+            inventory.add(&pickup.id);
+
+            // Not respawned when the level is reloaded
+            world_state
+                .entities
+                .entry(iid.0.clone())
+                .or_default()
+                .flags
+                .get_or_insert_default()
+                .insert(PICKED_UP_FLAG.into(), true);
+
+            commands.entity(entity).despawn();
+
+            info!("Item picked up: {}", pickup.id);
+
+            item_picked_up.write(ItemPickedUp {
+                id: pickup.id.clone(),
+            });
+        }
+    }
+}
+
+fn use_items(
+    mut events: MessageReader<UseItem>,
+    mut item_used: MessageWriter<ItemUsed>,
+    mut change_madness: MessageWriter<ChangeMadness>,
+    definitions_handle: Res<ItemDefinitionsHandle>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    mut players: Query<&mut Inventory, With<Player>>,
+) {
+    for event in events.read() {
+        let Some(definition) = definitions
+            .get(&definitions_handle.0)
+            .and_then(|definitions| definitions.0.get(&event.id))
+        else {
+            warn!("Unknown item {}", event.id);
+            continue;
+        };
+
+        for mut inventory in &mut players {
+            if !inventory.has_item(&event.id) {
+                continue;
+            }
+
+            if definition.consumable {
+                inventory.remove(&event.id);
+            }
+
+            if let Some(amount) = definition.madness {
+                change_madness.write(ChangeMadness { amount });
+            }
+
+            item_used.write(ItemUsed {
+                id: event.id.clone(),
+            });
+        }
+    }
+}
+
+fn update_ink_queries(
+    mut ink_queries: ResMut<InkQueries>,
+    players: Query<&Inventory, (With<Player>, Changed<Inventory>)>,
+) {
+    for inventory in players {
+        ink_queries.set(HAS_ITEM, inventory.items.iter().map(|item| item.id.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_and_removes_items() {
+        // Setup
+        let mut inventory = Inventory::default();
+
+        // Run
+        inventory.add("pills");
+        inventory.add("pills");
+        inventory.add("old_key");
+        let removed = inventory.remove("old_key");
+
+        // Check
+        assert!(removed);
+        assert!(!inventory.remove("old_key"));
+        assert_eq!(
+            inventory.items,
+            vec![InventoryItem {
+                id: "pills".into(),
+                count: 2
+            }]
+        );
+    }
+}
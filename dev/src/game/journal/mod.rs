@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    custom_asset_types::ron_asset::{RonAsset, ron_asset_plugin},
+    dialog_system::{
+        DialogTagEvent,
+        external_functions::{InkCall, InkExternalFunctions, InkQueries},
+    },
+    global::{GameState, loader::LoadingData},
+    map::npc::NpcName,
+};
+
+const CLUES_FILE_PATH: &str = "data/journal.clues.ron";
+/// Ink tag and external function granting a clue: `# clue: torn_letter`, `~ grant_clue("torn_letter")`
+const GRANT_CLUE: &str = "grant_clue";
+const CLUE_TAG: &str = "clue";
+/// Ink query telling whether a clue was found: `{has_clue("torn_letter"): ...}`
+const HAS_CLUE: &str = "has_clue";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClueDefinition {
+    pub title: String,
+    pub description: String,
+}
+
+/// Every clue of the game, keyed by id
+#[derive(Asset, TypePath, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct ClueDefinitions(pub HashMap<String, ClueDefinition>);
+
+impl RonAsset for ClueDefinitions {
+    const EXTENSIONS: &'static [&'static str] = &["clues.ron"];
+}
+
+#[derive(Resource, Default)]
+struct ClueDefinitionsHandle(Handle<ClueDefinitions>);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Clue {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    /// Who or what gave the clue away
+    pub source: String,
+}
+
+/// Clues found by the player, in the order they were found
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Journal {
+    pub clues: Vec<Clue>,
+}
+
+impl Journal {
+    pub fn has_clue(&self, id: &str) -> bool {
+        self.clues.iter().any(|clue| clue.id == id)
+    }
+}
+
+/// Gives the player a clue, `source_entity` being the NPC or object it comes from
+#[derive(Message)]
+pub struct GrantClue {
+    pub id: String,
+    pub source_entity: Entity,
+}
+
+#[derive(Message)]
+pub struct ClueAcquired {
+    pub clue: Clue,
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(ron_asset_plugin::<ClueDefinitions>);
+    app.add_message::<GrantClue>();
+    app.add_message::<ClueAcquired>();
+    app.init_resource::<Journal>();
+    app.add_systems(Startup, register_ink_functions);
+    app.add_systems(OnEnter(GameState::InGame), load_clue_definitions);
+    app.add_systems(
+        Update,
+        (grant_clues_from_tags, grant_clues, update_ink_queries)
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn register_ink_functions(mut external_functions: ResMut<InkExternalFunctions>) {
+    external_functions.register(GRANT_CLUE, grant_clue);
+    external_functions.register_query(HAS_CLUE);
+}
+
+fn grant_clue(call: &InkCall, commands: &mut Commands) {
+    if let Some(id) = call.string(0) {
+        commands.write_message(GrantClue {
+            id: id.into(),
+            source_entity: call.source_entity,
+        });
+    } else {
+        warn!("{} expects a clue id", GRANT_CLUE);
+    }
+}
+
+fn load_clue_definitions(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_data: ResMut<LoadingData>,
+) {
+    let handle = asset_server.load(CLUES_FILE_PATH);
+
+    loading_data.loading_assets.push(handle.clone().into());
+
+    commands.insert_resource(ClueDefinitionsHandle(handle));
+}
+
+fn grant_clues_from_tags(
+    mut tag_events: MessageReader<DialogTagEvent>,
+    mut grant_clue: MessageWriter<GrantClue>,
+) {
+    for event in tag_events.read() {
+        if event.key == CLUE_TAG {
+            grant_clue.write(GrantClue {
+                id: event.value.clone(),
+                source_entity: event.source_entity,
+            });
+        }
+    }
+}
+
+fn grant_clues(
+    mut events: MessageReader<GrantClue>,
+    mut clue_acquired: MessageWriter<ClueAcquired>,
+    mut journal: ResMut<Journal>,
+    definitions_handle: Res<ClueDefinitionsHandle>,
+    definitions: Res<Assets<ClueDefinitions>>,
+    sources: Query<(Option<&NpcName>, Option<&Name>)>,
+) {
+    for event in events.read() {
+        if journal.has_clue(&event.id) {
+            continue;
+        }
+
+        let Some(definition) = definitions
+            .get(&definitions_handle.0)
+            .and_then(|definitions| definitions.0.get(&event.id))
+        else {
+            warn!("Unknown clue {}", event.id);
+            continue;
+        };
+
+        let source = match sources.get(event.source_entity) {
+            Ok((Some(npc_name), _)) => npc_name.0.clone(),
+            Ok((None, Some(name))) => name.to_string(),
+            _ => "Unknown".into(),
+        };
+
+        let clue = Clue {
+            id: event.id.clone(),
+            title: definition.title.clone(),
+            description: definition.description.clone(),
+            source,
+        };
+
+        journal.clues.push(clue.clone());
+
+        info!("Clue acquired: {}", clue.title);
+
+        clue_acquired.write(ClueAcquired { clue });
+    }
+}
+
+fn update_ink_queries(journal: Res<Journal>, mut ink_queries: ResMut<InkQueries>) {
+    if journal.is_changed() {
+        ink_queries.set(HAS_CLUE, journal.clues.iter().map(|clue| clue.id.clone()));
+    }
+}
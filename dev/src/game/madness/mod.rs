@@ -0,0 +1,355 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    camera::DistortionIntensity,
+    dialog_system::external_functions::{InkCall, InkExternalFunctions},
+    global::GameState,
+    map::{
+        Arrival, ChangeLevel, CurrentLevelInfos,
+        variants::{LevelVariant, switch_variant_level},
+    },
+    player::Player,
+    save::load_game,
+};
+
+pub const MAX_MADNESS: f32 = 100.;
+const DEFAULT_MADNESS: f32 = 30.;
+/// Madness of the player waking up at the checkpoint after fainting
+const RECOVERED_MADNESS: f32 = 40.;
+/// Ink external function: `~ change_madness(10)`
+const CHANGE_MADNESS: &str = "change_madness";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MadnessPhase {
+    /// Easy mode, madness barely rises
+    HighOnPills,
+    Normal,
+    /// Bonuses, but madness rises faster
+    Stressed,
+    /// More monsters and hallucinations
+    Mad,
+    /// The player passes out and wakes up at the last checkpoint
+    Faint,
+}
+
+impl MadnessPhase {
+    pub fn from_value(value: f32) -> Self {
+        match value {
+            value if value < 15. => Self::HighOnPills,
+            value if value < 50. => Self::Normal,
+            value if value < 80. => Self::Stressed,
+            value if value < MAX_MADNESS => Self::Mad,
+            _ => Self::Faint,
+        }
+    }
+
+    /// How distorted the screen looks in this phase
+    fn distortion(&self) -> f32 {
+        match self {
+            Self::HighOnPills | Self::Normal => 0.,
+            Self::Stressed => 0.25,
+            Self::Mad => 0.6,
+            Self::Faint => 1.,
+        }
+    }
+
+    /// Variant of the levels shown in this phase, see [`LevelVariant`]
+    pub fn level_variant(&self) -> Option<&'static str> {
+        match self {
+            Self::HighOnPills => Some("pills"),
+            Self::Normal | Self::Faint => None,
+            Self::Stressed => Some("stressed"),
+            Self::Mad => Some("mad"),
+        }
+    }
+
+    /// Multiplies the madness gained in this phase
+    fn gain_multiplier(&self) -> f32 {
+        match self {
+            Self::HighOnPills => 0.5,
+            Self::Stressed => 1.5,
+            _ => 1.,
+        }
+    }
+}
+
+/// Madness gauge of the player, from 0 to [`MAX_MADNESS`]
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Madness {
+    pub value: f32,
+}
+
+impl Default for Madness {
+    fn default() -> Self {
+        Self {
+            value: DEFAULT_MADNESS,
+        }
+    }
+}
+
+impl Madness {
+    pub fn phase(&self) -> MadnessPhase {
+        MadnessPhase::from_value(self.value)
+    }
+
+    /// Applies a raise, scaled by the current phase, or a decrease
+    pub fn change(&mut self, amount: f32) {
+        let amount = if amount > 0. {
+            amount * self.phase().gain_multiplier()
+        } else {
+            amount
+        };
+
+        self.value = (self.value + amount).clamp(0., MAX_MADNESS);
+    }
+}
+
+/// Raises the madness of the player, or lowers it with a negative amount.
+/// Written by zones, dialogs and items
+#[derive(Message)]
+pub struct ChangeMadness {
+    pub amount: f32,
+}
+
+#[derive(Message)]
+pub struct MadnessPhaseChanged {
+    pub previous: MadnessPhase,
+    pub phase: MadnessPhase,
+}
+
+/// Where the player wakes up after fainting
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointLocation {
+    pub level: String,
+    pub grid_coords: (i32, i32),
+}
+
+/// Last checkpoint reached, the level the player first appeared in until then
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint(pub Option<CheckpointLocation>);
+
+pub fn plugin(app: &mut App) {
+    app.add_message::<ChangeMadness>();
+    app.add_message::<MadnessPhaseChanged>();
+    app.init_resource::<Checkpoint>();
+    app.add_systems(Startup, register_ink_functions);
+    app.add_systems(
+        Update,
+        (
+            add_madness,
+            set_first_checkpoint,
+            change_madness,
+            distort_screen,
+            faint_to_checkpoint,
+            set_level_variant,
+        )
+            .chain()
+            // A loaded save replaces the madness and the level variant
+            .after(load_game)
+            // The variant switch skips levels changed by fainting
+            .before(switch_variant_level)
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn register_ink_functions(mut external_functions: ResMut<InkExternalFunctions>) {
+    external_functions.register(CHANGE_MADNESS, change_madness_from_ink);
+}
+
+fn change_madness_from_ink(call: &InkCall, commands: &mut Commands) {
+    if let Some(amount) = call.float(0) {
+        commands.write_message(ChangeMadness { amount });
+    } else {
+        warn!("{} expects an amount", CHANGE_MADNESS);
+    }
+}
+
+fn add_madness(mut commands: Commands, players: Query<Entity, (Added<Player>, Without<Madness>)>) {
+    for player in players {
+        commands.entity(player).insert(Madness::default());
+    }
+}
+
+fn set_first_checkpoint(
+    mut checkpoint: ResMut<Checkpoint>,
+    level_infos: Res<CurrentLevelInfos>,
+    players: Query<&GridCoords, Added<Player>>,
+) {
+    for grid_coords in players {
+        if checkpoint.0.is_none() {
+            checkpoint.0 = Some(CheckpointLocation {
+                level: level_infos.identifier.clone(),
+                grid_coords: (grid_coords.x, grid_coords.y),
+            });
+        }
+    }
+}
+
+fn change_madness(
+    mut events: MessageReader<ChangeMadness>,
+    mut phase_changed: MessageWriter<MadnessPhaseChanged>,
+    players: Query<&mut Madness, With<Player>>,
+) {
+    let amounts: Vec<f32> = events.read().map(|event| event.amount).collect();
+
+    if amounts.is_empty() {
+        return;
+    }
+
+    for mut madness in players {
+        let previous = madness.phase();
+
+        for amount in &amounts {
+            madness.change(*amount);
+        }
+
+        let phase = madness.phase();
+
+        if phase != previous {
+            info!("Madness phase changed: {:?} -> {:?}", previous, phase);
+
+            phase_changed.write(MadnessPhaseChanged { previous, phase });
+        }
+    }
+}
+
+fn distort_screen(
+    mut events: MessageReader<MadnessPhaseChanged>,
+    mut distortion: ResMut<DistortionIntensity>,
+) {
+    if let Some(event) = events.read().last() {
+        distortion.value = event.phase.distortion();
+    }
+}
+
+/// Wakes the player up at the checkpoint, the madness going back down
+/// through [`ChangeMadness`] to write the phase change out of `Faint`
+fn faint_to_checkpoint(
+    mut events: MessageReader<MadnessPhaseChanged>,
+    mut change_madness: MessageWriter<ChangeMadness>,
+    mut change_level: MessageWriter<ChangeLevel>,
+    checkpoint: Res<Checkpoint>,
+    players: Query<&Madness, With<Player>>,
+) {
+    for event in events.read() {
+        if event.phase != MadnessPhase::Faint {
+            continue;
+        }
+
+        for madness in players {
+            change_madness.write(ChangeMadness {
+                amount: RECOVERED_MADNESS - madness.value,
+            });
+        }
+
+        if let Some(location) = &checkpoint.0 {
+            change_level.write(ChangeLevel {
+                identifier: location.level.clone(),
+                arrival: Some(Arrival {
+                    grid_coords: location.grid_coords.into(),
+                    facing: None,
+                }),
+            });
+        }
+    }
+}
+
+fn set_level_variant(
+    mut level_variant: ResMut<LevelVariant>,
+    players: Query<&Madness, (With<Player>, Changed<Madness>)>,
+) {
+    for madness in players {
+        level_variant.set_if_neq(LevelVariant(
+            madness.phase().level_variant().map(Into::into),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_values_to_phases() {
+        assert_eq!(MadnessPhase::from_value(0.), MadnessPhase::HighOnPills);
+        assert_eq!(MadnessPhase::from_value(15.), MadnessPhase::Normal);
+        assert_eq!(MadnessPhase::from_value(50.), MadnessPhase::Stressed);
+        assert_eq!(MadnessPhase::from_value(80.), MadnessPhase::Mad);
+        assert_eq!(MadnessPhase::from_value(MAX_MADNESS), MadnessPhase::Faint);
+    }
+
+    #[test]
+    fn scales_raises_with_phase() {
+        // Setup
+        let mut stressed = Madness { value: 60. };
+        let mut high = Madness { value: 10. };
+
+        // Run
+        stressed.change(10.);
+        high.change(-20.);
+
+        // Check
+        assert_eq!(stressed.value, 75.);
+        assert_eq!(high.value, 0.);
+    }
+
+    #[test]
+    fn writes_phase_changes() {
+        // Setup
+        let mut app = App::new();
+        app.add_message::<ChangeMadness>();
+        app.add_message::<MadnessPhaseChanged>();
+        app.add_systems(Update, change_madness);
+        app.world_mut().spawn((Player, Madness { value: 45. }));
+        app.world_mut().write_message(ChangeMadness { amount: 10. });
+
+        // Run
+        app.update();
+
+        // Check
+        let messages = app.world().resource::<Messages<MadnessPhaseChanged>>();
+        let changes: Vec<_> = messages
+            .iter_current_update_messages()
+            .map(|event| (event.previous, event.phase))
+            .collect();
+
+        assert_eq!(
+            changes,
+            vec![(MadnessPhase::Normal, MadnessPhase::Stressed)]
+        );
+    }
+
+    #[test]
+    fn faint_recovers_through_phase_change() {
+        // Setup
+        let mut app = App::new();
+        app.add_message::<ChangeMadness>();
+        app.add_message::<MadnessPhaseChanged>();
+        app.add_message::<ChangeLevel>();
+        app.init_resource::<Checkpoint>();
+        app.add_systems(Update, (change_madness, faint_to_checkpoint).chain());
+        let player = app.world_mut().spawn((Player, Madness { value: 95. })).id();
+        app.world_mut().write_message(ChangeMadness { amount: 10. });
+
+        // Run
+        app.update();
+        app.update();
+
+        // Check
+        let messages = app.world().resource::<Messages<MadnessPhaseChanged>>();
+        let changes: Vec<_> = messages
+            .iter_current_update_messages()
+            .map(|event| (event.previous, event.phase))
+            .collect();
+
+        assert_eq!(changes, vec![(MadnessPhase::Faint, MadnessPhase::Normal)]);
+        assert_eq!(
+            app.world().get::<Madness>(player),
+            Some(&Madness {
+                value: RECOVERED_MADNESS
+            })
+        );
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use bevy::{
+    color::palettes::css::{SADDLE_BROWN, SLATE_GRAY},
+    prelude::*,
+};
+use bevy_ecs_ldtk::EntityInstance;
+use thiserror::Error;
+
+use crate::game::{
+    audio::object_audio::{PlayObjectAudio, SpatialAudioParameters},
+    dialog_system::{
+        DialogFilePath, DialogKnot, DialogState, Narration, RunDialogEvent,
+        story_variables::StoryVariables,
+    },
+    global::{GameState, despawn_entity_on_level_change},
+    items::{Inventory, ItemPickedUp},
+    journal::GrantClue,
+    map::{
+        GRID_SIZE, InstanceIid,
+        interactions::{ACTIVATE_AUDIO, Interactable},
+        npc::{AvatarFilePath, NpcName},
+        utils,
+        world_state::StateFlags,
+    },
+    physics::colliders::{Collider, Intangible},
+};
+
+const IDENTIFIER: &str = "Action";
+const FIELDS: [&str; 4] = ["Kind", "Text", "Target", "Dialog"];
+const OPEN_FLAG: &str = "open";
+const ON_FLAG: &str = "on";
+const GIVEN_FLAG: &str = "given";
+const DEFAULT_LOCKED_MESSAGE: &str = "It's locked.";
+
+/// Built-in actions, picked by the `Kind` field of the LDtk `Action` entity
+#[derive(Component, Clone, Debug, PartialEq)]
+#[require(Interactable = Interactable::ACTION)]
+pub enum ActionKind {
+    /// Shows `Text`, granting the clue `Target` if any
+    Examine { text: String, clue: Option<String> },
+    /// Blocks the way until opened with the item `Target` if any, showing `Text` while locked
+    OpenDoor {
+        key: Option<String>,
+        locked_message: String,
+    },
+    /// Switches on and off, mirrored in the Ink variable `Target` if any
+    ToggleLever { variable: Option<String> },
+    /// Gives the item `Target` once
+    GiveItem { id: String },
+    /// Runs the Ink file `Dialog` from the knot `Target`
+    StartKnot { dialog: String, knot: String },
+    /// Plays the audio file `Target`
+    PlayAudio { file_path: String },
+}
+
+/// Possible errors that can be produced while reading an LDtk `Action` entity
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ActionKindError {
+    #[error("Unknown action kind \"{0}\"")]
+    UnknownKind(String),
+    #[error("Action kind \"{kind}\" requires the field \"{field}\"")]
+    MissingField { kind: String, field: String },
+}
+
+impl ActionKind {
+    pub fn from_fields(fields: &HashMap<String, String>) -> Result<Self, ActionKindError> {
+        let kind = fields
+            .get(FIELDS[0])
+            .map(String::as_str)
+            .unwrap_or_default();
+        let optional = |field: &str| fields.get(field).filter(|value| !value.is_empty()).cloned();
+        let required = |field: &str| {
+            optional(field).ok_or_else(|| ActionKindError::MissingField {
+                kind: kind.into(),
+                field: field.into(),
+            })
+        };
+
+        match kind {
+            "examine" => Ok(Self::Examine {
+                text: required(FIELDS[1])?,
+                clue: optional(FIELDS[2]),
+            }),
+            "open_door" => Ok(Self::OpenDoor {
+                key: optional(FIELDS[2]),
+                locked_message: optional(FIELDS[1]).unwrap_or(DEFAULT_LOCKED_MESSAGE.into()),
+            }),
+            "toggle_lever" => Ok(Self::ToggleLever {
+                variable: optional(FIELDS[2]),
+            }),
+            "give_item" => Ok(Self::GiveItem {
+                id: required(FIELDS[2])?,
+            }),
+            "start_knot" => Ok(Self::StartKnot {
+                dialog: required(FIELDS[3])?,
+                knot: optional(FIELDS[2]).unwrap_or_default(),
+            }),
+            "play_audio" => Ok(Self::PlayAudio {
+                file_path: required(FIELDS[2])?,
+            }),
+            _ => Err(ActionKindError::UnknownKind(kind.into())),
+        }
+    }
+
+    /// Components needed by the kind, besides the kind itself
+    fn bundle(&self, commands: &mut EntityCommands) {
+        match self {
+            Self::Examine { text, .. } => {
+                commands.insert(Narration(text.clone()));
+            }
+            Self::OpenDoor { locked_message, .. } => {
+                commands.insert((
+                    Narration(locked_message.clone()),
+                    // Placeholders until doors and levers get their own sprites
+                    Sprite::from_color(SADDLE_BROWN, Vec2::splat(GRID_SIZE as f32)),
+                    Collider::default(),
+                    StateFlags::default(),
+                ));
+            }
+            Self::ToggleLever { .. } => {
+                commands.insert((
+                    Sprite::from_color(SLATE_GRAY, Vec2::new(4., GRID_SIZE as f32 / 2.)),
+                    StateFlags::default(),
+                ));
+            }
+            Self::GiveItem { .. } => {
+                commands.insert(StateFlags::default());
+            }
+            Self::StartKnot { dialog, knot } => {
+                commands.insert((
+                    DialogFilePath(dialog.clone()),
+                    DialogKnot(knot.clone()),
+                    DialogState::default(),
+                    NpcName::default(),
+                    AvatarFilePath::default(),
+                ));
+            }
+            Self::PlayAudio { file_path } => {
+                commands.insert(SpatialAudioParameters::new(HashMap::from([(
+                    ACTIVATE_AUDIO.to_string(),
+                    file_path.clone(),
+                )])));
+            }
+        }
+    }
+}
+
+impl super::Action for ActionKind {
+    fn activate(&self, context: &mut super::ActionContext) {
+        let entity = context.entity;
+        let activator = context.activator;
+        let grid_coords = context.grid_coords;
+
+        match self {
+            Self::Examine { clue, .. } => {
+                context.commands.write_message(RunDialogEvent {
+                    source_entity: entity,
+                    choice_index: None,
+                });
+
+                if let Some(clue) = clue {
+                    context.commands.write_message(GrantClue {
+                        id: clue.clone(),
+                        source_entity: entity,
+                    });
+                }
+            }
+            Self::OpenDoor { key, .. } => {
+                let key = key.clone();
+
+                context.commands.queue(move |world: &mut World| {
+                    if let Some(key) = key
+                        && !world
+                            .get::<Inventory>(activator)
+                            .is_some_and(|inventory| inventory.has_item(&key))
+                    {
+                        info!("The door at {:?} needs {}", grid_coords, key);
+
+                        world.write_message(RunDialogEvent {
+                            source_entity: entity,
+                            choice_index: None,
+                        });
+                        return;
+                    }
+
+                    set_flag(world, entity, OPEN_FLAG, |_| true);
+                });
+            }
+            Self::ToggleLever { variable } => {
+                let variable = variable.clone();
+
+                context.commands.queue(move |world: &mut World| {
+                    let on = set_flag(world, entity, ON_FLAG, |on| !on);
+
+                    if let Some(variable) = variable {
+                        world.resource_mut::<StoryVariables>().set(variable, on);
+                    }
+                });
+            }
+            Self::GiveItem { id } => {
+                let id = id.clone();
+
+                context.commands.queue(move |world: &mut World| {
+                    let given = world
+                        .get::<StateFlags>(entity)
+                        .is_some_and(|flags| flags.0.get(GIVEN_FLAG) == Some(&true));
+
+                    if given {
+                        return;
+                    }
+
+                    match world.get_mut::<Inventory>(activator) {
+                        Some(mut inventory) => inventory.add(&id),
+                        None => return,
+                    }
+
+                    set_flag(world, entity, GIVEN_FLAG, |_| true);
+                    world.write_message(ItemPickedUp { id });
+                });
+            }
+            Self::StartKnot { .. } => {
+                context.commands.write_message(RunDialogEvent {
+                    source_entity: entity,
+                    choice_index: None,
+                });
+            }
+            Self::PlayAudio { .. } => {
+                context.commands.write_message(PlayObjectAudio {
+                    entity,
+                    audio_id: ACTIVATE_AUDIO.into(),
+                });
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            despawn_entity_on_level_change::<ActionKind>,
+            spawn_entity_instance,
+            super::activate::<ActionKind>,
+            sync_doors,
+            sync_levers,
+        )
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+/// Updates a flag of the actionable, returning its new value.
+/// `Action` entities are not resizable, so the activated tile is the whole actionable
+fn set_flag(world: &mut World, entity: Entity, flag: &str, update: impl Fn(bool) -> bool) -> bool {
+    let Some(mut flags) = world.get_mut::<StateFlags>(entity) else {
+        return false;
+    };
+
+    let value = update(flags.0.get(flag).copied().unwrap_or_default());
+
+    flags.0.insert(flag.into(), value);
+
+    value
+}
+
+fn spawn_entity_instance(
+    mut commands: Commands,
+    new_entity_instances: Query<(&EntityInstance, &Transform), Added<EntityInstance>>,
+) {
+    for (entity_instance, transform) in new_entity_instances.iter() {
+        if entity_instance.identifier != IDENTIFIER {
+            continue;
+        }
+
+        let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
+
+        let kind = match ActionKind::from_fields(&fields.strings) {
+            Ok(kind) => kind,
+            Err(err) => {
+                error!("Action {}: {}", entity_instance.iid, err);
+                continue;
+            }
+        };
+
+        let full_span_grid_coords = utils::full_span_grid_coords(
+            entity_instance.width,
+            entity_instance.height,
+            transform.translation,
+            GRID_SIZE,
+        );
+
+        for grid_coords in full_span_grid_coords {
+            let translation = bevy_ecs_ldtk::utils::grid_coords_to_translation(
+                grid_coords,
+                IVec2::splat(GRID_SIZE),
+            )
+            .extend(transform.translation.z);
+
+            let mut action = commands.spawn((
+                Transform {
+                    translation,
+                    scale: Vec3::ONE,
+                    ..*transform
+                },
+                kind.clone(),
+                Name::new(format!("Action ({})", fields.strings[FIELDS[0]])),
+                InstanceIid(entity_instance.iid.clone()),
+                grid_coords,
+            ));
+
+            kind.bundle(&mut action);
+        }
+    }
+}
+
+fn sync_doors(
+    mut commands: Commands,
+    doors: Query<(Entity, &ActionKind, &StateFlags, &mut Visibility), Changed<StateFlags>>,
+) {
+    for (entity, kind, flags, mut visibility) in doors {
+        if !matches!(kind, ActionKind::OpenDoor { .. }) {
+            continue;
+        }
+
+        if flags.0.get(OPEN_FLAG) == Some(&true) {
+            *visibility = Visibility::Hidden;
+            commands.entity(entity).insert(Intangible);
+        } else {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Intangible>();
+        }
+    }
+}
+
+fn sync_levers(levers: Query<(&ActionKind, &StateFlags, &mut Sprite), Changed<StateFlags>>) {
+    for (kind, flags, mut sprite) in levers {
+        if matches!(kind, ActionKind::ToggleLever { .. }) {
+            sprite.flip_x = flags.0.get(ON_FLAG) == Some(&true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_kinds_from_fields() {
+        // Setup
+        let door = HashMap::from([
+            ("Kind".to_string(), "open_door".to_string()),
+            ("Target".to_string(), "old_key".to_string()),
+        ]);
+        let examine = HashMap::from([("Kind".to_string(), "examine".to_string())]);
+        let unknown = HashMap::from([("Kind".to_string(), "dance".to_string())]);
+
+        // Run
+        let door = ActionKind::from_fields(&door);
+        let examine = ActionKind::from_fields(&examine);
+        let unknown = ActionKind::from_fields(&unknown);
+
+        // Check
+        assert_eq!(
+            door,
+            Ok(ActionKind::OpenDoor {
+                key: Some("old_key".into()),
+                locked_message: DEFAULT_LOCKED_MESSAGE.into(),
+            })
+        );
+        assert_eq!(
+            examine,
+            Err(ActionKindError::MissingField {
+                kind: "examine".into(),
+                field: "Text".into()
+            })
+        );
+        assert_eq!(unknown, Err(ActionKindError::UnknownKind("dance".into())));
+    }
+}
@@ -1,27 +1,46 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-use crate::game::player::Activate;
+use crate::game::map::interactions::{Interacted, InteractionKind};
 
-mod dummy;
+pub mod kinds;
+
+/// What an activated action can act upon
+pub struct ActionContext<'a, 'w, 's> {
+    pub commands: &'a mut Commands<'w, 's>,
+    /// The activated actionable
+    pub entity: Entity,
+    /// The entity activating it, usually the player
+    pub activator: Entity,
+    pub grid_coords: GridCoords,
+}
 
 pub trait Action {
-    fn activate(&self) -> ();
+    /// World access goes through `context.commands`, e.g. `commands.queue`
+    fn activate(&self, context: &mut ActionContext);
 }
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins(dummy::plugin);
+    app.add_plugins(kinds::plugin);
 }
 
 pub fn activate<T: Component + Action>(
-    mut activate_msg: MessageReader<Activate>,
-    actionables: Query<(&GridCoords, &T), With<T>>,
+    mut commands: Commands,
+    mut interacted: MessageReader<Interacted>,
+    actionables: Query<(&GridCoords, &T)>,
 ) {
-    for msg in activate_msg.read() {
-        for (grid_coords, action) in actionables {
-            if msg.grid_coords == (*grid_coords).into() {
-                action.activate();
-            }
+    for event in interacted.read() {
+        if event.kind != InteractionKind::Action {
+            continue;
+        }
+
+        if let Ok((grid_coords, action)) = actionables.get(event.target) {
+            action.activate(&mut ActionContext {
+                commands: &mut commands,
+                entity: event.target,
+                activator: event.activator,
+                grid_coords: *grid_coords,
+            });
         }
     }
 }
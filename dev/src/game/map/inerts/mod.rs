@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::AseSlice;
 use bevy_ecs_ldtk::{EntityInstance, GridCoords};
 
-use crate::game::map::GRID_SIZE;
+use crate::game::map::{GRID_SIZE, InstanceIid, interactions::Interactable};
 
 pub mod torch;
 
@@ -28,6 +28,7 @@ pub fn spawn_object<T: Component + MapObject>(
             commands.spawn((
                 T::aseslice(&server),
                 Sprite::default(),
+                InstanceIid(entity_instance.iid.clone()),
                 GridCoords {
                     ..entity_instance.grid.into()
                 },
@@ -39,8 +40,42 @@ pub fn spawn_object<T: Component + MapObject>(
                     .extend(OBJECT_Z_DEPTH),
                     ..Default::default()
                 },
+                // Plays the object `activate` audio when interacted with
+                Interactable::OBJECT,
                 T::new(),
             ));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_aseprite_ultra::prelude::Aseprite;
+
+    use super::*;
+    use crate::game::map::inerts::torch::Torch;
+
+    #[test]
+    fn spawns_interactable_objects() {
+        // Setup
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+        app.init_asset::<Aseprite>();
+        app.add_systems(Update, spawn_object::<Torch>);
+        app.world_mut().spawn(EntityInstance {
+            identifier: "Torch".into(),
+            ..Default::default()
+        });
+
+        // Run
+        app.update();
+
+        // Check
+        let mut objects = app
+            .world_mut()
+            .query_filtered::<&Interactable, With<Torch>>();
+        let interactables: Vec<_> = objects.iter(app.world()).copied().collect();
+
+        assert_eq!(interactables, vec![Interactable::OBJECT]);
+    }
+}
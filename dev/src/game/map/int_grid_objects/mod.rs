@@ -9,6 +9,35 @@ pub struct WallBundle {
     wall: Wall,
 }
 
+#[derive(Default, Component, Debug, Clone)]
+pub struct Water;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct WaterBundle {
+    water: Water,
+}
+
+/// One way tile, only walked through southward
+#[derive(Default, Component, Debug, Clone)]
+pub struct Ledge;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct LedgeBundle {
+    ledge: Ledge,
+}
+
+/// Invisible wall for NPCs and enemies only
+#[derive(Default, Component, Debug, Clone)]
+pub struct NpcBarrier;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct NpcBarrierBundle {
+    npc_barrier: NpcBarrier,
+}
+
 pub fn plugin(app: &mut App) {
     app.register_ldtk_int_cell::<WallBundle>(1);
+    app.register_ldtk_int_cell::<WaterBundle>(2);
+    app.register_ldtk_int_cell::<LedgeBundle>(3);
+    app.register_ldtk_int_cell::<NpcBarrierBundle>(4);
 }
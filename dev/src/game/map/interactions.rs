@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+
+use crate::game::{
+    audio::object_audio::PlayObjectAudio, global::GameState, physics::colliders::Intangible,
+    player::Activate,
+};
+
+/// Object audio played when the player interacts with an NPC, an object or an audio action
+pub const ACTIVATE_AUDIO: &str = "activate";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteractionKind {
+    Talk,
+    Action,
+    /// Plays the `activate` object audio
+    Object,
+}
+
+/// Something the player can interact with from the `ActionZone`.
+/// When several share the tile, only the highest priority one is interacted with
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interactable {
+    pub priority: i32,
+    pub kind: InteractionKind,
+}
+
+impl Interactable {
+    pub const NPC: Self = Self {
+        priority: 2,
+        kind: InteractionKind::Talk,
+    };
+    pub const ACTION: Self = Self {
+        priority: 1,
+        kind: InteractionKind::Action,
+    };
+    pub const OBJECT: Self = Self {
+        priority: 0,
+        kind: InteractionKind::Object,
+    };
+}
+
+#[derive(Message, Clone, Debug)]
+pub struct Interacted {
+    pub target: Entity,
+    /// The entity interacting, usually the player
+    pub activator: Entity,
+    pub kind: InteractionKind,
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_message::<Interacted>();
+    app.add_systems(
+        Update,
+        (dispatch_interactions, interact_with_objects)
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+/// Picks the highest priority interactable. Ties go to the smallest entity,
+/// which is only stable within a frame, as entity ids are recycled
+pub fn resolve(
+    candidates: impl IntoIterator<Item = (Entity, Interactable)>,
+) -> Option<(Entity, Interactable)> {
+    candidates
+        .into_iter()
+        .max_by(|(a, a_interactable), (b, b_interactable)| {
+            a_interactable
+                .priority
+                .cmp(&b_interactable.priority)
+                .then(b.cmp(a))
+        })
+}
+
+fn dispatch_interactions(
+    mut activate_msg: MessageReader<Activate>,
+    mut interacted: MessageWriter<Interacted>,
+    interactables: Query<(Entity, &GridCoords, &Interactable), Without<Intangible>>,
+) {
+    for msg in activate_msg.read() {
+        let candidates = interactables
+            .iter()
+            .filter(|(_, grid_coords, _)| msg.grid_coords == (**grid_coords).into())
+            .map(|(entity, _, interactable)| (entity, *interactable));
+
+        if let Some((target, interactable)) = resolve(candidates) {
+            interacted.write(Interacted {
+                target,
+                activator: msg.entity,
+                kind: interactable.kind,
+            });
+        }
+    }
+}
+
+fn interact_with_objects(
+    mut interacted: MessageReader<Interacted>,
+    mut object_audio: MessageWriter<PlayObjectAudio>,
+) {
+    for event in interacted.read() {
+        if event.kind == InteractionKind::Object {
+            object_audio.write(PlayObjectAudio {
+                entity: event.target,
+                audio_id: ACTIVATE_AUDIO.into(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_by_priority() {
+        // Setup
+        let mut world = World::new();
+        let first_action = world.spawn_empty().id();
+        let second_action = world.spawn_empty().id();
+        let npc = world.spawn_empty().id();
+        let object = world.spawn_empty().id();
+
+        // Run
+        let with_npc = resolve([
+            (object, Interactable::OBJECT),
+            (npc, Interactable::NPC),
+            (first_action, Interactable::ACTION),
+        ]);
+        let without_npc = resolve([
+            (second_action, Interactable::ACTION),
+            (object, Interactable::OBJECT),
+            (first_action, Interactable::ACTION),
+        ]);
+        let reversed = resolve([
+            (first_action, Interactable::ACTION),
+            (object, Interactable::OBJECT),
+            (second_action, Interactable::ACTION),
+        ]);
+
+        // Check
+        assert_eq!(with_npc, Some((npc, Interactable::NPC)));
+        assert_eq!(
+            without_npc.map(|(_, interactable)| interactable),
+            Some(Interactable::ACTION)
+        );
+        // Ties do not depend on the query order
+        assert_eq!(without_npc, reversed);
+        assert_eq!(resolve([]), None);
+    }
+}
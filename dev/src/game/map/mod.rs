@@ -1,25 +1,47 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-use crate::game::global::{
-    GameState,
-    loader::{LoadingData, LoadingState},
+use crate::game::{
+    global::{
+        GameState,
+        loader::{LoadingData, LoadingState},
+    },
+    player::{Facing, Player, Teleported},
 };
 
 mod actionables;
 pub mod inerts;
 pub mod int_grid_objects;
+pub mod interactions;
 pub mod npc;
 pub mod utils;
-mod zones;
+pub mod variants;
+pub mod world_state;
+pub mod zones;
 
 pub const GRID_SIZE: i32 = 16;
 
 #[derive(Message)]
 pub struct ChangeLevel {
-    identifier: String,
+    pub identifier: String,
+    /// Where to place the player once the level is spawned,
+    /// overriding the portal the player is coming from
+    pub arrival: Option<Arrival>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Arrival {
+    pub grid_coords: IVec2,
+    pub facing: Option<Facing>,
 }
 
+/// LDtk iid of the entity instance a gameplay entity was spawned from
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceIid(pub String);
+
+#[derive(Resource, Default)]
+pub struct PendingArrival(Option<Arrival>);
+
 #[derive(Resource, Default, Debug)]
 pub struct CurrentLevelInfos {
     pub identifier: String,
@@ -32,13 +54,18 @@ pub fn plugin(app: &mut App) {
         ..Default::default()
     });
     app.insert_resource(LevelSelection::index(0));
+    app.init_resource::<PendingArrival>();
 
     app.add_message::<ChangeLevel>();
 
     app.add_systems(OnEnter(GameState::InGame), map_setup);
     app.add_systems(
         Update,
-        (set_current_level_identifier, change_level)
+        (
+            set_current_level_identifier,
+            change_level,
+            place_player_on_arrival,
+        )
             .run_if(in_state(GameState::InGame))
             .run_if(in_state(LoadingState::Done)),
     );
@@ -46,16 +73,69 @@ pub fn plugin(app: &mut App) {
     app.add_plugins(int_grid_objects::plugin);
     app.add_plugins(zones::plugin);
     app.add_plugins(npc::plugin);
+    app.add_plugins(interactions::plugin);
     app.add_plugins(actionables::plugin);
     app.add_plugins(inerts::plugin);
+    app.add_plugins(world_state::plugin);
+    app.add_plugins(variants::plugin);
 }
 
 pub fn change_level(
+    mut commands: Commands,
     mut events: MessageReader<ChangeLevel>,
     mut level_selection: ResMut<LevelSelection>,
+    mut pending_arrival: ResMut<PendingArrival>,
+    level_infos: Res<CurrentLevelInfos>,
+    level_variant: Res<variants::LevelVariant>,
+    ldtk_world: Single<(Entity, &LdtkProjectHandle)>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
 ) {
+    let (ldtk_world, ldtk_project_handle) = ldtk_world.into_inner();
+
     for event in events.read() {
-        *level_selection = LevelSelection::Identifier(event.identifier.clone());
+        // Portals lead to base levels, swapped for their sibling in the current variant
+        let identifier = match ldtk_project_assets.get(ldtk_project_handle) {
+            Some(ldtk_project) => variants::variant_level_identifier(
+                ldtk_project
+                    .iter_raw_levels()
+                    .map(|level| level.identifier.as_str()),
+                &event.identifier,
+                &level_variant,
+            ),
+            None => event.identifier.clone(),
+        };
+
+        // Level entities are despawned on every level change,
+        // so staying on the same level means respawning it
+        if identifier == level_infos.identifier {
+            commands.entity(ldtk_world).insert(Respawn);
+        }
+
+        *level_selection = LevelSelection::Identifier(identifier);
+        pending_arrival.0 = event.arrival.clone();
+    }
+}
+
+/// Teleports the player to the pending arrival once the level is in place,
+/// which happens after portals placed the player on spawn
+fn place_player_on_arrival(
+    mut level_messages: MessageReader<LevelEvent>,
+    mut pending_arrival: ResMut<PendingArrival>,
+    mut teleport_message: MessageWriter<Teleported>,
+    players: Query<Entity, With<Player>>,
+) {
+    for level_event in level_messages.read() {
+        if let LevelEvent::Transformed(_) = level_event
+            && let Some(arrival) = pending_arrival.0.take()
+        {
+            for player in players {
+                teleport_message.write(Teleported {
+                    entity: player,
+                    grid_coords: arrival.grid_coords,
+                    facing: arrival.facing,
+                });
+            }
+        }
     }
 }
 
@@ -91,7 +171,8 @@ fn set_current_level_identifier(
                 .unwrap();
 
             *level_infos = CurrentLevelInfos {
-                coming_from: Some(level_infos.identifier.clone()),
+                // Portals only know about base levels
+                coming_from: Some(variants::base_identifier(&level_infos.identifier).into()),
                 identifier: level.identifier.clone(),
             };
         }
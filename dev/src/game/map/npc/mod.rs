@@ -1,33 +1,69 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::AseSlice;
 use bevy_ecs_ldtk::{EntityInstance, GridCoords};
 use bevy_tweening::*;
 use rand::prelude::*;
+use serde::Deserialize;
 
 use crate::game::{
+    audio::object_audio::{PlayObjectAudio, SpatialAudioParameters},
+    custom_asset_types::ron_asset::{RonAsset, ron_asset_plugin},
     dialog_system::{DialogEndedEvent, DialogFilePath, DialogKnot, DialogState, RunDialogEvent},
-    global::GameState,
+    global::{GameState, despawn_entity_on_level_change, loader::LoadingData},
     map::{
-        GRID_SIZE,
-        zones::{Zones, wander_zones::WanderZone},
+        GRID_SIZE, InstanceIid,
+        interactions::{ACTIVATE_AUDIO, Interactable, Interacted, InteractionKind},
+        utils,
+        world_state::PersistPosition,
+        zones::{ZoneOccupant, Zones, wander_zones::WanderZone},
     },
-    physics::colliders::{Collider, LevelColliders},
-    player::{Activate, JITTER_THRESHOLD},
+    physics::colliders::{Collider, CollisionLayer, LevelColliders},
+    player::JITTER_THRESHOLD,
     tick::{MainTick, MainTickCounter, TickDelta},
 };
 
-pub mod dummy_npc;
-
+const NPCS_FILE_PATH: &str = "data/npcs.npcs.ron";
+const IDENTIFIER: &str = "Npc";
+const FIELDS: [&str; 1] = ["Id"];
 const NPC_Z_DEPTH: f32 = 2.;
 
-trait Npc {
-    fn identifier() -> String;
-    fn aseslice(server: &Res<AssetServer>) -> AseSlice;
-    fn new() -> impl Bundle;
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct NpcDefinition {
+    pub name: String,
+    pub aseprite: String,
+    /// Slice of the aseprite file displayed
+    pub slice: String,
+    pub avatar: String,
+    pub dialog: String,
+    /// Knot the dialog starts from, the top of the file when empty
+    #[serde(default)]
+    pub knot: String,
+    /// Roams randomly inside the wander zones
+    #[serde(default)]
+    pub wander: bool,
+    /// Spatial audio file paths, keyed by audio id
+    #[serde(default)]
+    pub audio: HashMap<String, String>,
+}
+
+/// Every NPC of the game, keyed by the `Id` field of their LDtk entity
+#[derive(Asset, TypePath, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct NpcDefinitions(pub HashMap<String, NpcDefinition>);
+
+impl RonAsset for NpcDefinitions {
+    const EXTENSIONS: &'static [&'static str] = &["npcs.ron"];
 }
 
+#[derive(Resource, Default)]
+pub struct NpcDefinitionsHandle(pub Handle<NpcDefinitions>);
+
+#[derive(Component, Clone, Debug)]
+#[require(ZoneOccupant)]
+pub struct Npc;
+
 #[derive(Component)]
 struct Wanderer;
 
@@ -38,7 +74,14 @@ pub struct AvatarFilePath(pub String);
 pub struct NpcName(pub String);
 
 #[derive(Component)]
-#[require(DialogFilePath, DialogState, DialogKnot, AvatarFilePath, NpcName)]
+#[require(
+    DialogFilePath,
+    DialogState,
+    DialogKnot,
+    AvatarFilePath,
+    NpcName,
+    Interactable = Interactable::NPC
+)]
 struct Talkable;
 
 #[derive(Component)]
@@ -48,38 +91,101 @@ enum NpcStance {
 }
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins(dummy_npc::plugin);
+    app.add_plugins(ron_asset_plugin::<NpcDefinitions>);
+    // Loaded early, levels spawn their NPCs as soon as they are in game
+    app.add_systems(Startup, load_npc_definitions);
     app.add_systems(
         Update,
-        (wander, talk, end_talk).run_if(in_state(GameState::InGame)),
+        (
+            despawn_entity_on_level_change::<Npc>,
+            spawn_npc,
+            update_npc_position::<Npc>,
+            wander,
+            talk,
+            end_talk,
+        )
+            .run_if(in_state(GameState::InGame)),
     );
 }
 
-fn spawn_npc<T: Component + Npc>(
+fn load_npc_definitions(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_data: ResMut<LoadingData>,
+) {
+    let handle = asset_server.load(NPCS_FILE_PATH);
+
+    loading_data.loading_assets.push(handle.clone().into());
+
+    commands.insert_resource(NpcDefinitionsHandle(handle));
+}
+
+fn spawn_npc(
     mut commands: Commands,
     new_entity_instances: Query<&EntityInstance, Added<EntityInstance>>,
     server: Res<AssetServer>,
+    definitions_handle: Res<NpcDefinitionsHandle>,
+    definitions: Res<Assets<NpcDefinitions>>,
 ) {
     for entity_instance in new_entity_instances.iter() {
-        if entity_instance.identifier == T::identifier() {
-            commands.spawn((
-                T::aseslice(&server),
-                Sprite::default(),
-                GridCoords {
-                    ..entity_instance.grid.into()
-                },
-                Collider,
-                NpcStance::Roaming,
-                Transform {
-                    translation: bevy_ecs_ldtk::utils::grid_coords_to_translation(
-                        entity_instance.grid.into(),
-                        IVec2::splat(GRID_SIZE),
-                    )
-                    .extend(NPC_Z_DEPTH),
-                    ..Default::default()
-                },
-                T::new(),
-            ));
+        if entity_instance.identifier != IDENTIFIER {
+            continue;
+        }
+
+        let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
+
+        let Some(id) = fields.strings.get(FIELDS[0]) else {
+            warn!("Npc {} has no id", entity_instance.iid);
+            continue;
+        };
+
+        let Some(definition) = definitions
+            .get(&definitions_handle.0)
+            .and_then(|definitions| definitions.0.get(id))
+        else {
+            warn!("Unknown npc {id}");
+            continue;
+        };
+
+        let mut npc = commands.spawn((
+            Npc,
+            AseSlice {
+                name: definition.slice.clone(),
+                aseprite: server.load(&definition.aseprite),
+            },
+            Sprite::default(),
+            InstanceIid(entity_instance.iid.clone()),
+            PersistPosition,
+            GridCoords {
+                ..entity_instance.grid.into()
+            },
+            Collider::default(),
+            CollisionLayer::NPC,
+            NpcStance::Roaming,
+            Transform {
+                translation: bevy_ecs_ldtk::utils::grid_coords_to_translation(
+                    entity_instance.grid.into(),
+                    IVec2::splat(GRID_SIZE),
+                )
+                .extend(NPC_Z_DEPTH),
+                ..Default::default()
+            },
+            (
+                Talkable,
+                DialogFilePath(definition.dialog.clone()),
+                DialogKnot(definition.knot.clone()),
+                AvatarFilePath(definition.avatar.clone()),
+                NpcName(definition.name.clone()),
+            ),
+            Name::new(format!("Npc ({id})")),
+        ));
+
+        if definition.wander {
+            npc.insert(Wanderer);
+        }
+
+        if !definition.audio.is_empty() {
+            npc.insert(SpatialAudioParameters::new(definition.audio.clone()));
         }
     }
 }
@@ -97,7 +203,7 @@ fn wander(
 
         for (mut grid_coords, stance) in npc {
             if let NpcStance::Talking = stance {
-                return;
+                continue;
             }
 
             let move_distance = nums.choose(&mut rng);
@@ -123,7 +229,7 @@ fn wander(
                     *grid_coords - movement_vector.into()
                 };
 
-                if !level_colliders.in_collider(&destination)
+                if level_colliders.can_move(&grid_coords, &destination, CollisionLayer::NPC)
                     && wandering_zones.activated(&destination)
                 {
                     *grid_coords = destination;
@@ -133,7 +239,8 @@ fn wander(
     }
 }
 
-fn update_npc_position<T: Component + Npc>(
+/// Tweens the NPCs, or any other walking character, towards their new `GridCoords`
+pub fn update_npc_position<T: Component>(
     mut commands: Commands,
     npc: Query<(Entity, &Transform, &GridCoords), (With<T>, Changed<GridCoords>)>,
     tick_delta: Res<TickDelta>,
@@ -157,21 +264,31 @@ fn update_npc_position<T: Component + Npc>(
 }
 
 fn talk(
-    mut activate_event: MessageReader<Activate>,
+    mut interacted: MessageReader<Interacted>,
     mut dialog_event: MessageWriter<RunDialogEvent>,
-    talkable_npc: Query<(Entity, &GridCoords, &mut NpcStance), With<Talkable>>,
+    mut object_audio: MessageWriter<PlayObjectAudio>,
+    mut talkable_npc: Query<&mut NpcStance, With<Talkable>>,
 ) {
-    for (entity, grid_coords, mut stance) in talkable_npc {
-        for event in activate_event.read() {
-            if event.grid_coords == (*grid_coords).into() {
-                dialog_event.write(RunDialogEvent {
-                    source_entity: entity,
-                    choice_index: None,
-                });
-
-                *stance = NpcStance::Talking;
-            }
+    for event in interacted.read() {
+        if event.kind != InteractionKind::Talk {
+            continue;
         }
+
+        let Ok(mut stance) = talkable_npc.get_mut(event.target) else {
+            continue;
+        };
+
+        dialog_event.write(RunDialogEvent {
+            source_entity: event.target,
+            choice_index: None,
+        });
+
+        object_audio.write(PlayObjectAudio {
+            entity: event.target,
+            audio_id: ACTIVATE_AUDIO.into(),
+        });
+
+        *stance = NpcStance::Talking;
     }
 }
 
@@ -179,9 +296,11 @@ fn end_talk(
     talking_npc: Query<&mut NpcStance, With<Talkable>>,
     mut dialog_ended_event: MessageReader<DialogEndedEvent>,
 ) {
+    if dialog_ended_event.read().count() == 0 {
+        return;
+    }
+
     for mut stance in talking_npc {
-        for _ in dialog_ended_event.read() {
-            *stance = NpcStance::Roaming;
-        }
+        *stance = NpcStance::Roaming;
     }
 }
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
 use bevy::prelude::*;
-use bevy_ecs_ldtk::{EntityInstance, GridCoords, ldtk::FieldValue};
+use bevy_ecs_ldtk::{
+    EntityInstance, GridCoords,
+    ldtk::{FieldValue, ReferenceToAnEntityInstance},
+};
 
 pub fn entity_top_left_pixel_position(
     translation: Vec3,
@@ -72,23 +75,27 @@ pub struct EntityFieldsUnwrapped {
     pub floats: HashMap<String, f32>,
     pub integers: HashMap<String, i32>,
     pub bools: HashMap<String, bool>,
+    pub entity_refs: HashMap<String, ReferenceToAnEntityInstance>,
 }
 
 pub fn get_fields(
     entity_instance: &EntityInstance,
     identifiers: Vec<&str>,
 ) -> EntityFieldsUnwrapped {
-    let mut fields_iter = entity_instance.field_instances.iter();
-
     let mut results = EntityFieldsUnwrapped {
         strings: HashMap::new(),
         floats: HashMap::new(),
         integers: HashMap::new(),
         bools: HashMap::new(),
+        entity_refs: HashMap::new(),
     };
 
     for identifier in identifiers {
-        if let Some(raw_field) = fields_iter.find(|&field| field.identifier == identifier) {
+        if let Some(raw_field) = entity_instance
+            .field_instances
+            .iter()
+            .find(|&field| field.identifier == identifier)
+        {
             match &raw_field.value {
                 FieldValue::Int(Some(value)) => {
                     results.integers.insert(identifier.into(), *value);
@@ -102,6 +109,9 @@ pub fn get_fields(
                 FieldValue::String(Some(value)) => {
                     results.strings.insert(identifier.into(), value.into());
                 }
+                FieldValue::EntityRef(Some(value)) => {
+                    results.entity_refs.insert(identifier.into(), value.clone());
+                }
                 _ => (),
             };
         };
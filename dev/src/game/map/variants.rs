@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::game::{
+    global::GameState,
+    map::{Arrival, ChangeLevel, CurrentLevelInfos, InstanceIid, utils},
+    physics::colliders::{Collider, Intangible},
+    player::{Facing, Player},
+};
+
+/// Separates the name of a layer or level from its variant: `Walls@mad`, `Level_0@mad`
+pub const VARIANT_SEPARATOR: char = '@';
+/// Comma separated variants an LDtk entity exists in: `mad, stressed`
+const FIELDS: [&str; 1] = ["Variants"];
+
+/// Variant of the levels to show, `None` being the base levels.
+/// Layers and sibling levels suffixed with `@variant` only exist in that variant
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub struct LevelVariant(pub Option<String>);
+
+/// Entity that only exists, and only collides, in some variants
+#[derive(Component, Default, Debug, Clone)]
+pub struct Hallucination {
+    pub variants: Vec<String>,
+}
+
+impl Hallucination {
+    pub fn exists_in(&self, variant: &LevelVariant) -> bool {
+        variant
+            .0
+            .as_ref()
+            .is_some_and(|variant| self.variants.contains(variant))
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<LevelVariant>();
+    app.init_resource::<InstanceVariants>();
+    app.add_observer(record_instance_variants);
+    app.add_observer(mark_hallucination);
+    app.add_systems(
+        Update,
+        (
+            switch_variant_level.before(super::change_level),
+            toggle_variant_layers,
+            toggle_hallucinations,
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+/// `Level_0@mad` -> `Level_0`
+pub fn base_identifier(identifier: &str) -> &str {
+    identifier
+        .split(VARIANT_SEPARATOR)
+        .next()
+        .unwrap_or(identifier)
+}
+
+/// `(Walls, Some(mad))` from `Walls@mad`, `(Walls, None)` from `Walls`
+fn split_variant(identifier: &str) -> (&str, Option<&str>) {
+    match identifier.split_once(VARIANT_SEPARATOR) {
+        Some((name, variant)) => (name, Some(variant)),
+        None => (identifier, None),
+    }
+}
+
+/// Identifier of the sibling level matching the variant, the base level if there is none
+pub fn variant_level_identifier<'a>(
+    level_identifiers: impl IntoIterator<Item = &'a str>,
+    identifier: &str,
+    variant: &LevelVariant,
+) -> String {
+    let base = base_identifier(identifier);
+
+    if let Some(variant) = &variant.0 {
+        let variant_identifier = format!("{}{}{}", base, VARIANT_SEPARATOR, variant);
+
+        if level_identifiers
+            .into_iter()
+            .any(|level_identifier| level_identifier == variant_identifier)
+        {
+            return variant_identifier;
+        }
+    }
+
+    base.into()
+}
+
+/// Level to swap to when the variant changes, none when staying on the current level.
+/// A pending level change, like fainting to the checkpoint, wins as it picks the sibling itself
+fn variant_switch<'a>(
+    level_identifiers: impl IntoIterator<Item = &'a str>,
+    current: &str,
+    variant: &LevelVariant,
+    level_change_pending: bool,
+) -> Option<String> {
+    if level_change_pending {
+        return None;
+    }
+
+    let identifier = variant_level_identifier(level_identifiers, current, variant);
+
+    (identifier != current).then_some(identifier)
+}
+
+/// Swaps to the sibling level of the new variant, keeping the player where they stand.
+/// Runs after the madness systems changing the variant, and before the level change is applied
+pub fn switch_variant_level(
+    mut change_level: ResMut<Messages<ChangeLevel>>,
+    variant: Res<LevelVariant>,
+    level_infos: Res<CurrentLevelInfos>,
+    ldtk_project_entity: Single<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    players: Query<(&GridCoords, &Facing), With<Player>>,
+) {
+    if !variant.is_changed() {
+        return;
+    }
+
+    let Some(ldtk_project) = ldtk_project_assets.get(ldtk_project_entity.into_inner()) else {
+        return;
+    };
+
+    let Some(identifier) = variant_switch(
+        ldtk_project
+            .iter_raw_levels()
+            .map(|level| level.identifier.as_str()),
+        &level_infos.identifier,
+        &variant,
+        change_level.iter_current_update_messages().next().is_some(),
+    ) else {
+        return;
+    };
+
+    for (grid_coords, facing) in players {
+        change_level.write(ChangeLevel {
+            identifier: identifier.clone(),
+            arrival: Some(Arrival {
+                grid_coords: (*grid_coords).into(),
+                facing: Some(*facing),
+            }),
+        });
+    }
+}
+
+/// Shows the layers of the current variant, hides the others and their colliders
+fn toggle_variant_layers(
+    mut commands: Commands,
+    mut level_messages: MessageReader<LevelEvent>,
+    variant: Res<LevelVariant>,
+    layers: Query<(&LayerMetadata, &mut Visibility, Option<&Children>)>,
+    colliders: Query<(), With<Collider>>,
+) {
+    let level_transformed = level_messages
+        .read()
+        .any(|level_event| matches!(level_event, LevelEvent::Transformed(_)));
+
+    if !level_transformed && !variant.is_changed() {
+        return;
+    }
+
+    for (layer, mut visibility, children) in layers {
+        let (_, Some(layer_variant)) = split_variant(&layer.identifier) else {
+            continue;
+        };
+
+        let active = variant.0.as_deref() == Some(layer_variant);
+
+        *visibility = if active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+
+        for child in children.into_iter().flatten() {
+            if !colliders.contains(*child) {
+                continue;
+            }
+
+            if active {
+                commands.entity(*child).try_remove::<Intangible>();
+            } else {
+                commands.entity(*child).try_insert(Intangible);
+            }
+        }
+    }
+}
+
+/// Hallucinations of the LDtk entity instances by iid, read when the game entities
+/// are spawned from them. Bounded by the entities of the project, so never cleared
+#[derive(Resource, Default, Debug)]
+struct InstanceVariants(HashMap<String, Hallucination>);
+
+/// Records the variants of LDtk entity instances with a `Variants` field
+fn record_instance_variants(
+    add: On<Add, EntityInstance>,
+    mut instance_variants: ResMut<InstanceVariants>,
+    entity_instances: Query<&EntityInstance>,
+) {
+    let Ok(entity_instance) = entity_instances.get(add.entity) else {
+        return;
+    };
+
+    let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
+
+    let Some(variants) = fields.strings.get(FIELDS[0]) else {
+        return;
+    };
+
+    instance_variants.0.insert(
+        entity_instance.iid.clone(),
+        Hallucination {
+            variants: variants
+                .split(',')
+                .map(|variant| variant.trim().to_string())
+                .filter(|variant| !variant.is_empty())
+                .collect(),
+        },
+    );
+}
+
+/// Marks entities spawned from an instance with a `Variants` field as soon as they are spawned,
+/// hiding them on their first frame when they do not exist in the current variant
+fn mark_hallucination(
+    add: On<Add, InstanceIid>,
+    mut commands: Commands,
+    variant: Res<LevelVariant>,
+    instance_variants: Res<InstanceVariants>,
+    instance_iids: Query<&InstanceIid>,
+) {
+    let Ok(iid) = instance_iids.get(add.entity) else {
+        return;
+    };
+
+    let Some(hallucination) = instance_variants.0.get(&iid.0).cloned() else {
+        return;
+    };
+
+    if hallucination.exists_in(&variant) {
+        commands.entity(add.entity).insert(hallucination);
+    } else {
+        commands
+            .entity(add.entity)
+            .insert((hallucination, Visibility::Hidden, Intangible));
+    }
+}
+
+fn toggle_hallucinations(
+    mut commands: Commands,
+    variant: Res<LevelVariant>,
+    hallucinations: Query<(Entity, &Hallucination, &mut Visibility)>,
+) {
+    // New hallucinations are set up when marked
+    if !variant.is_changed() {
+        return;
+    }
+
+    for (entity, hallucination, mut visibility) in hallucinations {
+        if hallucination.exists_in(&variant) {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).try_remove::<Intangible>();
+        } else {
+            *visibility = Visibility::Hidden;
+            commands.entity(entity).try_insert(Intangible);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_variants() {
+        assert_eq!(base_identifier("Level_0@mad"), "Level_0");
+        assert_eq!(base_identifier("Level_0"), "Level_0");
+        assert_eq!(split_variant("Walls@mad"), ("Walls", Some("mad")));
+        assert_eq!(split_variant("Walls"), ("Walls", None));
+    }
+
+    #[test]
+    fn hallucinations_exist_in_their_variants() {
+        // Setup
+        let hallucination = Hallucination {
+            variants: vec!["mad".into()],
+        };
+
+        // Check
+        assert!(hallucination.exists_in(&LevelVariant(Some("mad".into()))));
+        assert!(!hallucination.exists_in(&LevelVariant(Some("stressed".into()))));
+        assert!(!hallucination.exists_in(&LevelVariant(None)));
+    }
+
+    #[test]
+    fn fainting_wins_over_the_variant_sibling() {
+        // Setup
+        let levels = ["Level_0", "Level_0@mad", "Level_1"];
+        let fainted = LevelVariant(None);
+
+        // Run
+        let while_fainting = variant_switch(levels, "Level_0@mad", &fainted, true);
+        let without_level_change = variant_switch(levels, "Level_0@mad", &fainted, false);
+        let without_sibling =
+            variant_switch(levels, "Level_1", &LevelVariant(Some("mad".into())), false);
+
+        // Check
+        assert_eq!(while_fainting, None);
+        assert_eq!(without_level_change, Some("Level_0".into()));
+        assert_eq!(without_sibling, None);
+    }
+}
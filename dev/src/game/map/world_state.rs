@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    dialog_system::DialogState,
+    global::GameState,
+    map::{GRID_SIZE, InstanceIid},
+};
+
+/// Marks LDtk spawned entities whose position is restored when their level is reloaded.
+/// Only meaningful for entities spanning a single tile, like NPCs
+#[derive(Component, Default)]
+pub struct PersistPosition;
+
+/// Custom flags persisted with an LDtk spawned entity across level reloads
+#[derive(Component, Default, Clone, Debug)]
+pub struct StateFlags(pub HashMap<String, bool>);
+
+/// Last known state of an LDtk spawned entity
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub dialog_state: Option<String>,
+    pub grid_coords: Option<(i32, i32)>,
+    pub flags: Option<HashMap<String, bool>>,
+}
+
+/// Snapshots of every LDtk spawned entity met during the run, keyed by iid.
+/// They are re-applied when an entity with the same iid is spawned again
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldState {
+    pub entities: HashMap<String, EntitySnapshot>,
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<WorldState>();
+    app.add_systems(
+        Update,
+        (
+            restore_snapshots,
+            record_dialog_states,
+            record_positions,
+            record_flags,
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn restore_snapshots(
+    mut commands: Commands,
+    world_state: Res<WorldState>,
+    entities: Query<
+        (
+            Entity,
+            &InstanceIid,
+            Option<&mut DialogState>,
+            Option<&mut GridCoords>,
+            Option<&mut Transform>,
+            Option<&mut StateFlags>,
+            Has<PersistPosition>,
+        ),
+        Added<InstanceIid>,
+    >,
+) {
+    for (entity, iid, dialog_state, grid_coords, transform, flags, persist_position) in entities {
+        let Some(snapshot) = world_state.entities.get(&iid.0) else {
+            continue;
+        };
+
+        if let Some(mut dialog_state) = dialog_state
+            && let Some(saved_state) = &snapshot.dialog_state
+        {
+            dialog_state.0 = saved_state.clone();
+        }
+
+        if persist_position
+            && let Some(mut grid_coords) = grid_coords
+            && let Some((x, y)) = snapshot.grid_coords
+        {
+            *grid_coords = GridCoords { x, y };
+
+            // Snap instead of letting movement tweens slide from the LDtk position
+            if let Some(mut transform) = transform {
+                transform.translation = bevy_ecs_ldtk::utils::grid_coords_to_translation(
+                    *grid_coords,
+                    IVec2::splat(GRID_SIZE),
+                )
+                .extend(transform.translation.z);
+            }
+        }
+
+        if let Some(saved_flags) = &snapshot.flags {
+            match flags {
+                Some(mut flags) => flags.0 = saved_flags.clone(),
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert(StateFlags(saved_flags.clone()));
+                }
+            }
+        }
+    }
+}
+
+fn record_dialog_states(
+    mut world_state: ResMut<WorldState>,
+    entities: Query<(&InstanceIid, &DialogState), Changed<DialogState>>,
+) {
+    for (iid, dialog_state) in entities {
+        world_state
+            .entities
+            .entry(iid.0.clone())
+            .or_default()
+            .dialog_state = Some(dialog_state.0.clone());
+    }
+}
+
+fn record_positions(
+    mut world_state: ResMut<WorldState>,
+    entities: Query<(&InstanceIid, &GridCoords), (With<PersistPosition>, Changed<GridCoords>)>,
+) {
+    for (iid, grid_coords) in entities {
+        world_state
+            .entities
+            .entry(iid.0.clone())
+            .or_default()
+            .grid_coords = Some((grid_coords.x, grid_coords.y));
+    }
+}
+
+fn record_flags(
+    mut world_state: ResMut<WorldState>,
+    entities: Query<(&InstanceIid, &StateFlags), Changed<StateFlags>>,
+) {
+    for (iid, flags) in entities {
+        world_state.entities.entry(iid.0.clone()).or_default().flags = Some(flags.0.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_npc(app: &mut App) -> Entity {
+        app.world_mut()
+            .spawn((
+                InstanceIid("npc-iid".into()),
+                PersistPosition,
+                GridCoords { x: 1, y: 1 },
+                Transform::default(),
+                StateFlags::default(),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn restores_respawned_entities() {
+        // Setup
+        let mut app = App::new();
+        app.init_resource::<WorldState>();
+        app.add_systems(
+            Update,
+            (
+                restore_snapshots,
+                record_dialog_states,
+                record_positions,
+                record_flags,
+            )
+                .chain(),
+        );
+
+        let npc = spawn_npc(&mut app);
+        app.update();
+
+        *app.world_mut().get_mut::<GridCoords>(npc).unwrap() = GridCoords { x: 4, y: 2 };
+        app.world_mut()
+            .get_mut::<StateFlags>(npc)
+            .unwrap()
+            .0
+            .insert("met".into(), true);
+        app.update();
+
+        // Run
+        app.world_mut().despawn(npc);
+        let npc = spawn_npc(&mut app);
+        app.update();
+
+        // Check
+        let world = app.world();
+
+        assert_eq!(
+            world.get::<GridCoords>(npc),
+            Some(&GridCoords { x: 4, y: 2 })
+        );
+        assert_eq!(
+            world.get::<Transform>(npc).unwrap().translation.truncate(),
+            bevy_ecs_ldtk::utils::grid_coords_to_translation(
+                GridCoords { x: 4, y: 2 },
+                IVec2::splat(GRID_SIZE)
+            )
+        );
+        assert_eq!(
+            world.get::<StateFlags>(npc).unwrap().0,
+            HashMap::from([("met".to_string(), true)])
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::game::{
+    global::{GameState, despawn_entity_on_level_change},
+    madness::{Checkpoint, CheckpointLocation},
+    map::{
+        CurrentLevelInfos,
+        zones::{ZoneEntered, Zones},
+    },
+    player::Player,
+};
+
+const IDENTIFIER: &str = "Checkpoint";
+
+/// The player wakes up on the last one walked on after fainting
+#[derive(Component, Default, Clone, Debug)]
+pub struct CheckpointZone;
+
+impl super::Zone for CheckpointZone {
+    fn identifier() -> String {
+        IDENTIFIER.into()
+    }
+
+    fn new(_entity_instance: &EntityInstance) -> impl Bundle {
+        CheckpointZone
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(Zones::<CheckpointZone> {
+        ..Default::default()
+    });
+    super::add_zone_messages::<CheckpointZone>(app);
+
+    app.add_systems(
+        Update,
+        (
+            super::empty_zones_cache::<CheckpointZone>,
+            despawn_entity_on_level_change::<CheckpointZone>,
+            super::spawn_zones::<CheckpointZone>,
+            super::cache_zones::<CheckpointZone>,
+            super::track_occupants::<CheckpointZone>,
+            activate,
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn activate(
+    mut zone_entered: MessageReader<ZoneEntered<CheckpointZone>>,
+    players: Query<(), With<Player>>,
+    level_infos: Res<CurrentLevelInfos>,
+    mut checkpoint: ResMut<Checkpoint>,
+) {
+    for entered in zone_entered.read() {
+        if players.contains(entered.entity) {
+            checkpoint.0 = Some(CheckpointLocation {
+                level: level_infos.identifier.clone(),
+                grid_coords: (entered.grid_coords.x, entered.grid_coords.y),
+            });
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::game::{
+    global::{GameState, despawn_entity_on_level_change},
+    madness::ChangeMadness,
+    map::{
+        utils,
+        zones::{ZoneStayed, Zones},
+    },
+    player::Player,
+    tick::{GameTempo, MainTickCounter},
+};
+
+const IDENTIFIER: &str = "MadnessZone";
+const FIELDS: [&str; 1] = ["AmountPerMeasure"];
+const DEFAULT_AMOUNT_PER_MEASURE: f32 = 1.;
+
+/// Changes the madness of the player every measure spent inside it
+#[derive(Component, Default, Clone, Debug)]
+pub struct MadnessZone {
+    amount_per_measure: f32,
+}
+
+impl MadnessZone {
+    pub fn raises_madness(&self) -> bool {
+        self.amount_per_measure > 0.
+    }
+}
+
+impl super::Zone for MadnessZone {
+    fn identifier() -> String {
+        IDENTIFIER.into()
+    }
+
+    fn new(entity_instance: &EntityInstance) -> impl Bundle {
+        let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
+
+        let amount_per_measure = fields.floats.get(FIELDS[0]).copied().unwrap_or_else(|| {
+            warn!(
+                "MadnessZone {} has no {}, using {}",
+                entity_instance.iid, FIELDS[0], DEFAULT_AMOUNT_PER_MEASURE
+            );
+
+            DEFAULT_AMOUNT_PER_MEASURE
+        });
+
+        MadnessZone { amount_per_measure }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(Zones::<MadnessZone> {
+        report_stays: true,
+        ..Default::default()
+    });
+    super::add_zone_messages::<MadnessZone>(app);
+
+    app.add_systems(
+        Update,
+        (
+            super::empty_zones_cache::<MadnessZone>,
+            despawn_entity_on_level_change::<MadnessZone>,
+            super::spawn_zones::<MadnessZone>,
+            super::cache_zones::<MadnessZone>,
+            super::track_occupants::<MadnessZone>,
+            activate,
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn activate(
+    mut zone_stayed: MessageReader<ZoneStayed<MadnessZone>>,
+    players: Query<(), With<Player>>,
+    main_tick_counter: Res<MainTickCounter>,
+    game_tempo: Res<GameTempo>,
+    mut event: MessageWriter<ChangeMadness>,
+) {
+    // Stays are reported on every note
+    let on_measure = main_tick_counter.on_measure(&game_tempo);
+
+    for stayed in zone_stayed.read() {
+        if on_measure && players.contains(stayed.entity) {
+            event.write(ChangeMadness {
+                amount: stayed.zone.amount_per_measure,
+            });
+        }
+    }
+}
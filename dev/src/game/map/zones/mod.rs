@@ -3,15 +3,61 @@ use std::collections::HashMap;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::{EntityInstance, GridCoords, LevelEvent};
 
-use crate::game::map::{GRID_SIZE, utils};
+use crate::game::{
+    map::{GRID_SIZE, InstanceIid, utils},
+    tick::MainTick,
+};
 
+mod checkpoints;
+pub mod madness_zones;
 mod music_zones;
 mod portals;
 pub mod wander_zones;
 
-#[derive(Default, Resource)]
+#[derive(Resource)]
 pub struct Zones<T: Component> {
     pub locations: HashMap<GridCoords, T>,
+    /// Iid of the zone instance on each tile, telling apart neighbouring zones
+    instances: HashMap<GridCoords, String>,
+    /// Zone instance each occupant stands in
+    occupants: HashMap<Entity, (String, T)>,
+    /// Writes a [`ZoneStayed`] for each occupant on every note
+    pub report_stays: bool,
+}
+
+impl<T: Component> Default for Zones<T> {
+    fn default() -> Self {
+        Self {
+            locations: HashMap::new(),
+            instances: HashMap::new(),
+            occupants: HashMap::new(),
+            report_stays: false,
+        }
+    }
+}
+
+/// Entities whose comings and goings through zones are reported, like the player and NPCs
+#[derive(Component, Default)]
+pub struct ZoneOccupant;
+
+#[derive(Message)]
+pub struct ZoneEntered<T: Component> {
+    pub entity: Entity,
+    pub zone: T,
+    pub grid_coords: GridCoords,
+}
+
+/// Also written for every occupant when the level changes or when it is despawned
+#[derive(Message)]
+pub struct ZoneExited<T: Component> {
+    pub entity: Entity,
+    pub zone: T,
+}
+
+#[derive(Message)]
+pub struct ZoneStayed<T: Component> {
+    pub entity: Entity,
+    pub zone: T,
 }
 
 trait Zone {
@@ -26,16 +72,34 @@ impl<T: Component> Zones<T> {
 }
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins((portals::plugin, wander_zones::plugin, music_zones::plugin));
+    app.add_plugins((
+        portals::plugin,
+        wander_zones::plugin,
+        music_zones::plugin,
+        madness_zones::plugin,
+        checkpoints::plugin,
+    ));
+}
+
+fn add_zone_messages<T: Component>(app: &mut App) {
+    app.add_message::<ZoneEntered<T>>();
+    app.add_message::<ZoneExited<T>>();
+    app.add_message::<ZoneStayed<T>>();
 }
 
 fn empty_zones_cache<T: Component>(
     mut level_zones: ResMut<Zones<T>>,
     mut level_messages: MessageReader<LevelEvent>,
+    mut exited: MessageWriter<ZoneExited<T>>,
 ) {
     for level_event in level_messages.read() {
         if let LevelEvent::Spawned(_) = level_event {
             level_zones.locations = HashMap::new();
+            level_zones.instances = HashMap::new();
+
+            for (entity, (_, zone)) in level_zones.occupants.drain() {
+                exited.write(ZoneExited { entity, zone });
+            }
         }
     }
 }
@@ -71,6 +135,7 @@ fn spawn_zones<T: Component + Zone>(
                         ..*transform
                     },
                     T::new(entity_instance),
+                    InstanceIid(entity_instance.iid.clone()),
                     grid_coords,
                 ));
             }
@@ -80,9 +145,145 @@ fn spawn_zones<T: Component + Zone>(
 
 fn cache_zones<T: Component + Clone>(
     mut level_zones: ResMut<Zones<T>>,
-    zones: Query<(&T, &GridCoords), Added<T>>,
+    zones: Query<(&T, &GridCoords, &InstanceIid), Added<T>>,
 ) {
-    for (zone, grid_coords) in zones {
+    for (zone, grid_coords, iid) in zones {
         level_zones.locations.insert(*grid_coords, zone.clone());
+        level_zones.instances.insert(*grid_coords, iid.0.clone());
+    }
+}
+
+/// Compares the zone instance of each occupant with the one it stood in,
+/// walking within the same zone instance is neither an exit nor an entrance
+fn track_occupants<T: Component + Clone>(
+    mut level_zones: ResMut<Zones<T>>,
+    occupants: Query<(Entity, Ref<GridCoords>), With<ZoneOccupant>>,
+    new_zones: Query<(), Added<T>>,
+    mut removed_occupants: RemovedComponents<ZoneOccupant>,
+    main_tick: Res<MainTick>,
+    mut entered: MessageWriter<ZoneEntered<T>>,
+    mut exited: MessageWriter<ZoneExited<T>>,
+    mut stayed: MessageWriter<ZoneStayed<T>>,
+) {
+    // Despawned occupants leave their zone too
+    for entity in removed_occupants.read() {
+        if let Some((_, zone)) = level_zones.occupants.remove(&entity) {
+            exited.write(ZoneExited { entity, zone });
+        }
+    }
+
+    // Occupants standing still are in newly spawned zones too
+    let zones_spawned = !new_zones.is_empty();
+
+    for (entity, grid_coords) in occupants {
+        if grid_coords.is_changed() || zones_spawned {
+            let current = level_zones.instances.get(&*grid_coords).cloned();
+            let previous = level_zones
+                .occupants
+                .get(&entity)
+                .map(|(iid, _)| iid.clone());
+
+            if current != previous {
+                if let Some((_, zone)) = level_zones.occupants.remove(&entity) {
+                    exited.write(ZoneExited { entity, zone });
+                }
+
+                if let Some(iid) = current
+                    && let Some(zone) = level_zones.locations.get(&*grid_coords).cloned()
+                {
+                    level_zones.occupants.insert(entity, (iid, zone.clone()));
+
+                    entered.write(ZoneEntered {
+                        entity,
+                        zone,
+                        grid_coords: *grid_coords,
+                    });
+                }
+
+                continue;
+            }
+        }
+
+        if level_zones.report_stays
+            && main_tick.timer.just_finished()
+            && let Some((_, zone)) = level_zones.occupants.get(&entity)
+        {
+            stayed.write(ZoneStayed {
+                entity,
+                zone: zone.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::zones::wander_zones::WanderZone;
+
+    fn step(app: &mut App, occupant: Entity, grid_coords: GridCoords) -> (usize, usize) {
+        *app.world_mut().get_mut::<GridCoords>(occupant).unwrap() = grid_coords;
+        app.update();
+
+        let world = app.world();
+        let entered = world
+            .resource::<Messages<ZoneEntered<WanderZone>>>()
+            .iter_current_update_messages()
+            .count();
+        let exited = world
+            .resource::<Messages<ZoneExited<WanderZone>>>()
+            .iter_current_update_messages()
+            .count();
+
+        (entered, exited)
+    }
+
+    #[test]
+    fn reports_entering_and_leaving_zone_instances() {
+        // Setup
+        let mut app = App::new();
+        add_zone_messages::<WanderZone>(&mut app);
+        app.insert_resource(Zones::<WanderZone>::default());
+        app.insert_resource(MainTick {
+            timer: Timer::from_seconds(1., TimerMode::Repeating),
+        });
+        app.add_systems(
+            Update,
+            (cache_zones::<WanderZone>, track_occupants::<WanderZone>).chain(),
+        );
+
+        for (x, iid) in [(0, "forest"), (1, "forest"), (2, "clearing")] {
+            app.world_mut()
+                .spawn((WanderZone, GridCoords::new(x, 0), InstanceIid(iid.into())));
+        }
+
+        let occupant = app
+            .world_mut()
+            .spawn((ZoneOccupant, GridCoords::new(0, 1)))
+            .id();
+
+        // Run
+        let outside = step(&mut app, occupant, GridCoords::new(0, 1));
+        let into_forest = step(&mut app, occupant, GridCoords::new(0, 0));
+        let within_forest = step(&mut app, occupant, GridCoords::new(1, 0));
+        let into_clearing = step(&mut app, occupant, GridCoords::new(2, 0));
+        let out_of_clearing = step(&mut app, occupant, GridCoords::new(2, 1));
+        let back_into_clearing = step(&mut app, occupant, GridCoords::new(2, 0));
+        app.world_mut().despawn(occupant);
+        app.update();
+        let despawned = app
+            .world()
+            .resource::<Messages<ZoneExited<WanderZone>>>()
+            .iter_current_update_messages()
+            .count();
+
+        // Check
+        assert_eq!(outside, (0, 0));
+        assert_eq!(into_forest, (1, 0));
+        assert_eq!(within_forest, (0, 0));
+        assert_eq!(into_clearing, (1, 1));
+        assert_eq!(out_of_clearing, (0, 1));
+        assert_eq!(back_into_clearing, (1, 0));
+        assert_eq!(despawned, 1);
     }
 }
@@ -4,7 +4,10 @@ use bevy_ecs_ldtk::prelude::*;
 use crate::game::{
     audio::music::PlaySong,
     global::{GameState, despawn_entity_on_level_change},
-    map::{utils, zones::Zones},
+    map::{
+        utils,
+        zones::{ZoneEntered, Zones},
+    },
     player::Player,
 };
 
@@ -17,12 +20,6 @@ pub struct MusicZone {
     part: String,
 }
 
-impl Zones<MusicZone> {
-    pub fn zone_on_gridcoord(&self, grid_coords: &GridCoords) -> Option<&MusicZone> {
-        self.locations.get(grid_coords)
-    }
-}
-
 impl super::Zone for MusicZone {
     fn identifier() -> String {
         IDENTIFIER.into()
@@ -55,6 +52,7 @@ pub fn plugin(app: &mut App) {
     app.insert_resource(Zones::<MusicZone> {
         ..Default::default()
     });
+    super::add_zone_messages::<MusicZone>(app);
 
     app.add_systems(
         Update,
@@ -63,6 +61,7 @@ pub fn plugin(app: &mut App) {
             despawn_entity_on_level_change::<MusicZone>,
             super::spawn_zones::<MusicZone>,
             super::cache_zones::<MusicZone>,
+            super::track_occupants::<MusicZone>,
             activate,
         )
             .chain()
@@ -70,18 +69,17 @@ pub fn plugin(app: &mut App) {
     );
 }
 
+/// Songs only change when entering a zone, not on every step inside it
 fn activate(
-    zones: Res<Zones<MusicZone>>,
-    players: Query<&GridCoords, (With<Player>, Changed<GridCoords>)>,
+    mut zone_entered: MessageReader<ZoneEntered<MusicZone>>,
+    players: Query<(), With<Player>>,
     mut event: MessageWriter<PlaySong>,
 ) {
-    for grid_coords in players {
-        if zones.activated(grid_coords)
-            && let Some(zone) = zones.zone_on_gridcoord(grid_coords)
-        {
+    for entered in zone_entered.read() {
+        if players.contains(entered.entity) {
             event.write(PlaySong {
-                song_title: zone.song_title.clone(),
-                part: zone.part.clone(),
+                song_title: entered.zone.song_title.clone(),
+                part: entered.zone.part.clone(),
             });
         }
     }
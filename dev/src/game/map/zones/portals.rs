@@ -2,25 +2,78 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
 use crate::game::{
+    dialog_system::{Narration, RunDialogEvent, story_variables::StoryVariables},
     global::{GameState, despawn_entity_on_level_change},
-    map::{ChangeLevel, CurrentLevelInfos, utils, zones::Zones},
-    player::{Player, Teleported},
+    items::Inventory,
+    map::{
+        ChangeLevel, CurrentLevelInfos, utils,
+        zones::{ZoneEntered, Zones},
+    },
+    player::{Facing, Player, Teleported},
 };
 
 const IDENTIFIER: &str = "Portal";
-const FIELDS: [&str; 1] = ["To"];
+const FIELDS: [&str; 6] = [
+    "To",
+    "Target",
+    "Facing",
+    "RequiredItem",
+    "RequiredFlag",
+    "LockedMessage",
+];
+const DEFAULT_LOCKED_MESSAGE: &str = "It won't open.";
 
 #[derive(Component, Default, Clone, Debug)]
 struct Portal {
+    iid: String,
     to: String,
+    /// Iid of the portal to arrive on in the `to` level,
+    /// the one leading back here when not set
+    target: Option<String>,
+    /// Facing of the player on arrival
+    facing: Option<Facing>,
+    conditions: PortalConditions,
 }
 
+/// What the player needs to go through a portal
+#[derive(Default, Clone, Debug, PartialEq)]
+struct PortalConditions {
+    required_item: Option<String>,
+    /// Ink boolean variable which must be true
+    required_flag: Option<String>,
+}
+
+impl PortalConditions {
+    fn met(&self, inventory: Option<&Inventory>, story_variables: &StoryVariables) -> bool {
+        let has_item = self
+            .required_item
+            .as_ref()
+            .is_none_or(|item| inventory.is_some_and(|inventory| inventory.has_item(item)));
+
+        let has_flag = self
+            .required_flag
+            .as_ref()
+            .is_none_or(|flag| story_variables.get_bool(flag) == Some(true));
+
+        has_item && has_flag
+    }
+}
+
+/// Where the player arrives once the level they are going to is spawned
+#[derive(Resource, Default)]
+struct PendingEntrance(Option<(Option<String>, Option<Facing>)>);
+
 #[derive(Component)]
 struct NotTeleportable;
 
 impl Zones<Portal> {
-    pub fn portal_on_gridcoord(&self, grid_coords: &GridCoords) -> Option<&Portal> {
-        self.locations.get(grid_coords)
+    /// Bottom left tile of the portal satisfying `predicate`
+    fn entrance(&self, predicate: impl Fn(&Portal) -> bool) -> Option<GridCoords> {
+        self.locations
+            .iter()
+            .filter(|(_, portal)| predicate(portal))
+            .map(|(grid_coords, _)| *grid_coords)
+            .min_by_key(|grid_coords| (grid_coords.y, grid_coords.x))
     }
 }
 
@@ -32,13 +85,46 @@ impl super::Zone for Portal {
     fn new(entity_instance: &EntityInstance) -> impl Bundle {
         let fields = utils::get_fields(entity_instance, FIELDS.to_vec());
 
-        if let Some(to_field) = fields.strings.get("To") {
+        let Some(to_field) = fields.strings.get(FIELDS[0]) else {
+            panic!("To field not found on entity instance")
+        };
+
+        let facing = fields.strings.get(FIELDS[2]).and_then(|name| {
+            let facing = Facing::from_name(name);
+
+            if facing.is_none() {
+                warn!(
+                    "Portal {} has an unknown facing {}",
+                    entity_instance.iid, name
+                );
+            }
+
+            facing
+        });
+
+        (
             Portal {
+                iid: entity_instance.iid.clone(),
                 to: to_field.into(),
-            }
-        } else {
-            panic!("To field not found on entity instance")
-        }
+                target: fields
+                    .entity_refs
+                    .get(FIELDS[1])
+                    .map(|target| target.entity_iid.clone()),
+                facing,
+                conditions: PortalConditions {
+                    required_item: fields.strings.get(FIELDS[3]).cloned(),
+                    required_flag: fields.strings.get(FIELDS[4]).cloned(),
+                },
+            },
+            // Shown when the conditions are not met
+            Narration(
+                fields
+                    .strings
+                    .get(FIELDS[5])
+                    .cloned()
+                    .unwrap_or(DEFAULT_LOCKED_MESSAGE.into()),
+            ),
+        )
     }
 }
 
@@ -46,6 +132,8 @@ pub fn plugin(app: &mut App) {
     app.insert_resource(Zones::<Portal> {
         ..Default::default()
     });
+    app.init_resource::<PendingEntrance>();
+    super::add_zone_messages::<Portal>(app);
 
     app.add_systems(
         Update,
@@ -54,6 +142,7 @@ pub fn plugin(app: &mut App) {
             despawn_entity_on_level_change::<Portal>,
             super::spawn_zones::<Portal>,
             super::cache_zones::<Portal>,
+            super::track_occupants::<Portal>,
             spawn_player_on_portal,
             activate,
             remove_not_teleportable,
@@ -65,48 +154,78 @@ pub fn plugin(app: &mut App) {
 
 fn activate(
     mut commands: Commands,
-    mut event: MessageWriter<ChangeLevel>,
-    portals: Res<Zones<Portal>>,
-    players: Query<
-        (Entity, &GridCoords),
-        (With<Player>, Changed<GridCoords>, Without<NotTeleportable>),
-    >,
+    mut zone_entered: MessageReader<ZoneEntered<Portal>>,
+    mut change_level: MessageWriter<ChangeLevel>,
+    mut dialog_event: MessageWriter<RunDialogEvent>,
+    mut pending_entrance: ResMut<PendingEntrance>,
+    story_variables: Res<StoryVariables>,
+    portals: Query<(Entity, &GridCoords), With<Portal>>,
+    players: Query<Option<&Inventory>, (With<Player>, Without<NotTeleportable>)>,
 ) {
-    for (entity, grid_coords) in players {
-        if portals.activated(grid_coords)
-            && let Some(portal) = portals.portal_on_gridcoord(grid_coords)
-        {
-            event.write(ChangeLevel {
-                identifier: portal.to.clone(),
-            });
-
-            commands.entity(entity).insert(NotTeleportable);
+    for entered in zone_entered.read() {
+        let Ok(inventory) = players.get(entered.entity) else {
+            continue;
+        };
+
+        let portal = &entered.zone;
+
+        // Not shown again until the player steps off the portal
+        commands.entity(entered.entity).insert(NotTeleportable);
+
+        if !portal.conditions.met(inventory, &story_variables) {
+            if let Some((portal_entity, _)) = portals
+                .iter()
+                .find(|(_, grid_coords)| **grid_coords == entered.grid_coords)
+            {
+                dialog_event.write(RunDialogEvent {
+                    source_entity: portal_entity,
+                    choice_index: None,
+                });
+            }
+
+            continue;
         }
+
+        pending_entrance.0 = Some((portal.target.clone(), portal.facing));
+
+        change_level.write(ChangeLevel {
+            identifier: portal.to.clone(),
+            arrival: None,
+        });
     }
 }
 
 fn spawn_player_on_portal(
-    level_portals: ResMut<Zones<Portal>>,
+    level_portals: Res<Zones<Portal>>,
     mut level_messages: MessageReader<LevelEvent>,
     mut teleport_message: MessageWriter<Teleported>,
+    mut pending_entrance: ResMut<PendingEntrance>,
     players: Query<Entity, With<Player>>,
     level_infos: Res<CurrentLevelInfos>,
 ) {
     for level_event in level_messages.read() {
         if let LevelEvent::Spawned(_) = level_event {
+            let (target, facing) = pending_entrance.0.take().unwrap_or_default();
+
+            // Targets in a base level are missing from its variants, which use the portal leading back
+            let entrance = target
+                .and_then(|target| level_portals.entrance(|portal| portal.iid == target))
+                .or_else(|| {
+                    let coming_from = level_infos.coming_from.as_ref()?;
+
+                    level_portals.entrance(|portal| portal.to == *coming_from)
+                });
+
+            let Some(grid_coords) = entrance else {
+                continue;
+            };
+
             for player in players {
-                for (grid_coords, portal) in level_portals.locations.iter() {
-                    if let Some(coming_from) = &level_infos.coming_from
-                        && portal.to == *coming_from
-                    {
-                        teleport_message.write(Teleported {
-                            entity: player,
-                            grid_coords: (*grid_coords).into(),
-                        });
-
-                        break;
-                    }
-                }
+                teleport_message.write(Teleported {
+                    entity: player,
+                    grid_coords: grid_coords.into(),
+                    facing,
+                });
             }
         }
     }
@@ -123,3 +242,32 @@ fn remove_not_teleportable(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portal_conditions_need_item_and_flag() {
+        // Setup
+        let conditions = PortalConditions {
+            required_item: Some("old_key".into()),
+            required_flag: Some("door_unlocked".into()),
+        };
+        let mut inventory = Inventory::default();
+        let mut story_variables = StoryVariables::default();
+
+        // Run
+        let without_anything = conditions.met(Some(&inventory), &story_variables);
+        inventory.add("old_key");
+        let without_flag = conditions.met(Some(&inventory), &story_variables);
+        story_variables.set("door_unlocked", true);
+        let with_both = conditions.met(Some(&inventory), &story_variables);
+
+        // Check
+        assert!(!without_anything);
+        assert!(!without_flag);
+        assert!(with_both);
+        assert!(PortalConditions::default().met(None, &story_variables));
+    }
+}
@@ -25,6 +25,7 @@ pub fn plugin(app: &mut App) {
     app.insert_resource(Zones::<WanderZone> {
         ..Default::default()
     });
+    super::add_zone_messages::<WanderZone>(app);
 
     app.add_systems(
         Update,
@@ -33,6 +34,7 @@ pub fn plugin(app: &mut App) {
             despawn_entity_on_level_change::<WanderZone>,
             super::spawn_zones::<WanderZone>,
             super::cache_zones::<WanderZone>,
+            super::track_occupants::<WanderZone>,
         )
             .run_if(in_state(GameState::InGame))
             .chain(),
@@ -1,53 +1,196 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::BitOr};
 
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
 use crate::game::{global::GameState, map::GRID_SIZE};
 
-mod wall;
+mod tiles;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
-pub struct Collider;
+const LAYER_COUNT: usize = 4;
 
+/// Set of collision layers. As a component, tells what a moving entity is
+#[derive(Component, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CollisionLayer(u8);
+
+impl CollisionLayer {
+    pub const PLAYER: Self = Self(1);
+    pub const NPC: Self = Self(1 << 1);
+    pub const ENEMY: Self = Self(1 << 2);
+    pub const PROJECTILE: Self = Self(1 << 3);
+    pub const ALL: Self = Self((1 << LAYER_COUNT) - 1);
+
+    fn indices(&self) -> impl Iterator<Item = usize> {
+        (0..LAYER_COUNT).filter(|index| self.0 & (1 << index) != 0)
+    }
+}
+
+impl BitOr for CollisionLayer {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Blocks the movers whose layer is in the mask
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Component)]
+pub struct Collider {
+    pub mask: CollisionLayer,
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Self {
+            mask: CollisionLayer::ALL,
+        }
+    }
+}
+
+impl Collider {
+    pub fn blocking(mask: CollisionLayer) -> Self {
+        Self { mask }
+    }
+}
+
+/// Collider ignored until the component is removed, like the walls of another level variant
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Intangible;
+
+/// Tile only entered moving in `direction`, and never left against it, like a ledge
+#[derive(Component, Clone, Copy, Debug)]
+pub struct OneWay {
+    pub direction: IVec2,
+}
+
+/// Tiles blocked by colliders, indexed by position.
+/// Several colliders may share a tile, like an NPC walking over a hallucinated wall
 #[derive(Default, Resource)]
 pub struct LevelColliders {
-    collider_locations: HashMap<Entity, GridCoords>,
+    /// Kept to free the previous tile of a moving or removed collider
+    collider_locations: HashMap<Entity, (GridCoords, CollisionLayer)>,
+    /// Number of colliders blocking each layer, on each blocked tile
+    occupancy: HashMap<GridCoords, [u32; LAYER_COUNT]>,
+    one_way_locations: HashMap<Entity, GridCoords>,
+    one_ways: HashMap<GridCoords, IVec2>,
     level_width: i32,
     level_height: i32,
 }
 
 impl LevelColliders {
-    pub fn in_collider(&self, grid_coords: &GridCoords) -> bool {
+    /// Whether a mover of this layer can't stand on the tile
+    pub fn in_collider(&self, grid_coords: &GridCoords, layer: CollisionLayer) -> bool {
         grid_coords.x < 0
             || grid_coords.y < 0
             || grid_coords.x >= self.level_width
             || grid_coords.y >= self.level_height
             || self
-                .collider_locations
-                .values()
-                .any(|grid_coord| grid_coord == grid_coords)
+                .occupancy
+                .get(grid_coords)
+                .is_some_and(|counts| layer.indices().any(|index| counts[index] > 0))
     }
+
+    /// Also checks one way tiles, `from` and `to` being neighbours
+    pub fn can_move(&self, from: &GridCoords, to: &GridCoords, layer: CollisionLayer) -> bool {
+        !self.in_collider(to, layer) && one_way_allows(&self.one_ways, from, to)
+    }
+
+    /// Width and height of the level, in tiles
+    pub fn level_size(&self) -> IVec2 {
+        IVec2::new(self.level_width, self.level_height)
+    }
+
+    /// Tiles blocking movers of this layer
+    pub fn collider_cells(&self, layer: CollisionLayer) -> impl Iterator<Item = &GridCoords> {
+        self.occupancy
+            .iter()
+            .filter(move |(_, counts)| layer.indices().any(|index| counts[index] > 0))
+            .map(|(grid_coords, _)| grid_coords)
+    }
+
+    pub fn one_ways(&self) -> &HashMap<GridCoords, IVec2> {
+        &self.one_ways
+    }
+
+    /// Places the collider, moving it if it was already known
+    pub fn insert(&mut self, entity: Entity, grid_coords: GridCoords, mask: CollisionLayer) {
+        if let Some(previous) = self.collider_locations.insert(entity, (grid_coords, mask)) {
+            if previous == (grid_coords, mask) {
+                return;
+            }
+
+            self.release(previous);
+        }
+
+        let counts = self.occupancy.entry(grid_coords).or_default();
+
+        for index in mask.indices() {
+            counts[index] += 1;
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(previous) = self.collider_locations.remove(&entity) {
+            self.release(previous);
+        }
+    }
+
+    fn release(&mut self, (grid_coords, mask): (GridCoords, CollisionLayer)) {
+        if let Some(counts) = self.occupancy.get_mut(&grid_coords) {
+            for index in mask.indices() {
+                counts[index] = counts[index].saturating_sub(1);
+            }
+
+            if counts.iter().all(|count| *count == 0) {
+                self.occupancy.remove(&grid_coords);
+            }
+        }
+    }
+}
+
+/// One way tiles are only entered moving in their direction, and never left against it
+pub fn one_way_allows(
+    one_ways: &HashMap<GridCoords, IVec2>,
+    from: &GridCoords,
+    to: &GridCoords,
+) -> bool {
+    let movement = IVec2::new(to.x - from.x, to.y - from.y);
+
+    one_ways
+        .get(to)
+        .is_none_or(|direction| *direction == movement)
+        && one_ways
+            .get(from)
+            .is_none_or(|direction| *direction != -movement)
 }
 
 pub fn plugin(app: &mut App) {
     app.init_resource::<LevelColliders>();
-    app.add_plugins(wall::plugin);
+    app.add_plugins(tiles::plugin);
     app.add_systems(
         Update,
         (
-            empty_collider_locations_cache,
             cache_level_bounds,
+            remove_collider_locations,
             cache_collider_locations,
-            update_collider_locations,
+            cache_one_ways,
         )
+            .chain()
             .run_if(in_state(GameState::InGame)),
     );
 }
 
-fn add_collider<T: Component>(mut commands: Commands, colliders: Query<Entity, Added<T>>) {
+/// Int grid tiles blocking movers
+trait ColliderTile {
+    fn collider() -> Collider;
+}
+
+fn add_collider<T: Component + ColliderTile>(
+    mut commands: Commands,
+    colliders: Query<Entity, Added<T>>,
+) {
     for entity in colliders {
-        commands.entity(entity).insert(Collider);
+        commands.entity(entity).insert(T::collider());
     }
 }
 
@@ -70,31 +213,206 @@ fn cache_level_bounds(
     }
 }
 
-fn empty_collider_locations_cache(
+/// Frees the tiles of despawned level entities and intangible colliders,
+/// while the player keeps its collider across levels
+fn remove_collider_locations(
     mut level_colliders: ResMut<LevelColliders>,
-    mut level_messages: MessageReader<LevelEvent>,
+    mut removed_colliders: RemovedComponents<Collider>,
+    intangibles: Query<Entity, Added<Intangible>>,
+    colliders: Query<(), (With<Collider>, Without<Intangible>)>,
 ) {
-    for level_event in level_messages.read() {
-        if let LevelEvent::Despawned(_) = level_event {
-            level_colliders.collider_locations = HashMap::new();
+    for entity in removed_colliders.read() {
+        // Removed then inserted again since the last run
+        if !colliders.contains(entity) {
+            level_colliders.remove(entity);
         }
     }
+
+    for entity in intangibles {
+        level_colliders.remove(entity);
+    }
 }
 
 fn cache_collider_locations(
     mut level_colliders: ResMut<LevelColliders>,
-    colliders: Query<(Entity, &GridCoords), With<Collider>>,
+    mut removed_intangibles: RemovedComponents<Intangible>,
+    colliders: Query<
+        (Entity, &GridCoords, &Collider),
+        (
+            Without<Intangible>,
+            Or<(Changed<Collider>, Changed<GridCoords>)>,
+        ),
+    >,
+    tangibles: Query<(&GridCoords, &Collider), Without<Intangible>>,
 ) {
-    for (entity, collider) in colliders {
-        level_colliders.collider_locations.insert(entity, *collider);
+    for (entity, grid_coords, collider) in colliders {
+        level_colliders.insert(entity, *grid_coords, collider.mask);
+    }
+
+    for entity in removed_intangibles.read() {
+        if let Ok((grid_coords, collider)) = tangibles.get(entity) {
+            level_colliders.insert(entity, *grid_coords, collider.mask);
+        }
     }
 }
 
-fn update_collider_locations(
+fn cache_one_ways(
     mut level_colliders: ResMut<LevelColliders>,
-    colliders: Query<(Entity, &GridCoords), (With<Collider>, Changed<GridCoords>)>,
+    mut removed_one_ways: RemovedComponents<OneWay>,
+    one_ways: Query<(Entity, &GridCoords, &OneWay), Added<OneWay>>,
 ) {
-    for (entity, collider) in colliders {
-        level_colliders.collider_locations.insert(entity, *collider);
+    for entity in removed_one_ways.read() {
+        if let Some(grid_coords) = level_colliders.one_way_locations.remove(&entity) {
+            level_colliders.one_ways.remove(&grid_coords);
+        }
+    }
+
+    for (entity, grid_coords, one_way) in one_ways {
+        level_colliders
+            .one_way_locations
+            .insert(entity, *grid_coords);
+        level_colliders
+            .one_ways
+            .insert(*grid_coords, one_way.direction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test::Bencher;
+
+    use super::*;
+
+    const BENCH_SIZE: i32 = 64;
+
+    /// Previous implementation, scanning every collider on each query
+    #[derive(Default)]
+    struct LinearColliders {
+        collider_locations: HashMap<Entity, GridCoords>,
+    }
+
+    impl LinearColliders {
+        fn in_collider(&self, grid_coords: &GridCoords) -> bool {
+            self.collider_locations
+                .values()
+                .any(|grid_coord| grid_coord == grid_coords)
+        }
+    }
+
+    /// A wall every other tile, like a dense int grid layer
+    fn bench_colliders() -> Vec<(Entity, GridCoords)> {
+        let mut world = World::new();
+
+        (0..BENCH_SIZE * BENCH_SIZE / 2)
+            .map(|index| {
+                (
+                    world.spawn_empty().id(),
+                    GridCoords {
+                        x: (index * 2) % BENCH_SIZE,
+                        y: (index * 2) / BENCH_SIZE,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_colliders_sharing_a_tile() {
+        // Setup
+        let mut level_colliders = LevelColliders {
+            level_width: 4,
+            level_height: 4,
+            ..Default::default()
+        };
+        let mut world = World::new();
+        let wall = world.spawn_empty().id();
+        let npc = world.spawn_empty().id();
+        let tile = GridCoords { x: 1, y: 1 };
+
+        // Run
+        level_colliders.insert(wall, tile, CollisionLayer::ALL);
+        level_colliders.insert(npc, tile, CollisionLayer::ALL);
+        level_colliders.insert(npc, GridCoords { x: 2, y: 1 }, CollisionLayer::ALL);
+        let still_blocked = level_colliders.in_collider(&tile, CollisionLayer::PLAYER);
+        level_colliders.remove(wall);
+
+        // Check
+        assert!(still_blocked);
+        assert!(!level_colliders.in_collider(&tile, CollisionLayer::PLAYER));
+        assert!(level_colliders.in_collider(&GridCoords { x: 2, y: 1 }, CollisionLayer::PLAYER));
+    }
+
+    #[test]
+    fn blocks_movers_by_layer_and_direction() {
+        // Setup
+        let mut level_colliders = LevelColliders {
+            level_width: 4,
+            level_height: 4,
+            ..Default::default()
+        };
+        let mut world = World::new();
+        let barrier = world.spawn_empty().id();
+        let ledge = GridCoords { x: 2, y: 2 };
+
+        // Run
+        level_colliders.insert(
+            barrier,
+            GridCoords { x: 1, y: 1 },
+            CollisionLayer::NPC | CollisionLayer::ENEMY,
+        );
+        level_colliders.one_ways.insert(ledge, IVec2::NEG_Y);
+
+        // Check
+        assert!(level_colliders.in_collider(&GridCoords { x: 1, y: 1 }, CollisionLayer::NPC));
+        assert!(!level_colliders.in_collider(&GridCoords { x: 1, y: 1 }, CollisionLayer::PLAYER));
+        assert!(level_colliders.can_move(
+            &GridCoords { x: 2, y: 3 },
+            &ledge,
+            CollisionLayer::PLAYER
+        ));
+        assert!(!level_colliders.can_move(
+            &GridCoords { x: 2, y: 1 },
+            &ledge,
+            CollisionLayer::PLAYER
+        ));
+        assert!(!level_colliders.can_move(
+            &ledge,
+            &GridCoords { x: 2, y: 3 },
+            CollisionLayer::PLAYER
+        ));
+    }
+
+    #[bench]
+    fn query_linear_colliders(bencher: &mut Bencher) {
+        let level_colliders = LinearColliders {
+            collider_locations: bench_colliders().into_iter().collect(),
+        };
+
+        bencher.iter(|| {
+            (0..BENCH_SIZE)
+                .filter(|x| level_colliders.in_collider(&GridCoords { x: *x, y: 1 }))
+                .count()
+        });
+    }
+
+    #[bench]
+    fn query_indexed_colliders(bencher: &mut Bencher) {
+        let mut level_colliders = LevelColliders {
+            level_width: BENCH_SIZE,
+            level_height: BENCH_SIZE,
+            ..Default::default()
+        };
+
+        for (entity, grid_coords) in bench_colliders() {
+            level_colliders.insert(entity, grid_coords, CollisionLayer::ALL);
+        }
+
+        bencher.iter(|| {
+            (0..BENCH_SIZE)
+                .filter(|x| {
+                    level_colliders.in_collider(&GridCoords { x: *x, y: 1 }, CollisionLayer::PLAYER)
+                })
+                .count()
+        });
     }
 }
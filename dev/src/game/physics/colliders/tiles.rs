@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::game::{
+    global::GameState,
+    map::int_grid_objects::{Ledge, NpcBarrier, Wall, Water},
+};
+
+use super::{Collider, ColliderTile, CollisionLayer, OneWay};
+
+impl ColliderTile for Wall {
+    fn collider() -> Collider {
+        Collider::default()
+    }
+}
+
+/// Projectiles fly over water
+impl ColliderTile for Water {
+    fn collider() -> Collider {
+        Collider::blocking(CollisionLayer::PLAYER | CollisionLayer::NPC | CollisionLayer::ENEMY)
+    }
+}
+
+/// Keeps NPCs and enemies out of an area, without the need of a wander zone
+impl ColliderTile for NpcBarrier {
+    fn collider() -> Collider {
+        Collider::blocking(CollisionLayer::NPC | CollisionLayer::ENEMY)
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            super::add_collider::<Wall>,
+            super::add_collider::<Water>,
+            super::add_collider::<NpcBarrier>,
+            add_ledges,
+        )
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+/// Ledges are jumped down, never climbed
+fn add_ledges(mut commands: Commands, ledges: Query<Entity, Added<Ledge>>) {
+    for entity in ledges {
+        commands.entity(entity).insert(OneWay {
+            direction: IVec2::NEG_Y,
+        });
+    }
+}
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 
 pub mod colliders;
+pub mod pathfinding;
 
 pub fn plugin(app: &mut App) {
     app.add_plugins(colliders::plugin);
+    app.add_plugins(pathfinding::plugin);
 }
@@ -0,0 +1,309 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+
+use crate::game::{
+    global::GameState,
+    physics::colliders::{CollisionLayer, LevelColliders, one_way_allows},
+    tick::MainTick,
+};
+
+const NEIGHBOURS: [GridCoords; 4] = [
+    GridCoords { x: 0, y: 1 },
+    GridCoords { x: 1, y: 0 },
+    GridCoords { x: 0, y: -1 },
+    GridCoords { x: -1, y: 0 },
+];
+
+/// Walkable tiles of the current level, for movers of a given layer
+#[derive(Default, Debug, Clone)]
+pub struct NavGrid {
+    size: IVec2,
+    blocked: HashSet<GridCoords>,
+    one_ways: HashMap<GridCoords, IVec2>,
+}
+
+impl NavGrid {
+    pub fn new(size: IVec2, blocked: impl IntoIterator<Item = GridCoords>) -> Self {
+        Self {
+            size,
+            blocked: blocked.into_iter().collect(),
+            one_ways: HashMap::new(),
+        }
+    }
+
+    pub fn for_layer(level_colliders: &LevelColliders, layer: CollisionLayer) -> Self {
+        Self {
+            one_ways: level_colliders.one_ways().clone(),
+            ..Self::new(
+                level_colliders.level_size(),
+                level_colliders.collider_cells(layer).copied(),
+            )
+        }
+    }
+
+    pub fn is_walkable(&self, grid_coords: &GridCoords) -> bool {
+        grid_coords.x >= 0
+            && grid_coords.y >= 0
+            && grid_coords.x < self.size.x
+            && grid_coords.y < self.size.y
+            && !self.blocked.contains(grid_coords)
+    }
+
+    /// A* over the four directions. The path excludes `from`, which may be blocked by the mover itself,
+    /// and ends on `to`. `None` when `to` can't be reached
+    pub fn find_path(&self, from: GridCoords, to: GridCoords) -> Option<Vec<GridCoords>> {
+        if !self.is_walkable(&to) && from != to {
+            return None;
+        }
+
+        self.search(from, to, false)
+    }
+
+    /// Path ending next to `to`, which may be blocked, like a tile occupied by the player
+    pub fn find_path_next_to(&self, from: GridCoords, to: GridCoords) -> Option<Vec<GridCoords>> {
+        self.search(from, to, true)
+    }
+
+    fn search(&self, from: GridCoords, to: GridCoords, next_to: bool) -> Option<Vec<GridCoords>> {
+        let arrived = |grid_coords: GridCoords| {
+            let distance = (grid_coords.x - to.x).abs() + (grid_coords.y - to.y).abs();
+
+            if next_to {
+                distance == 1
+            } else {
+                distance == 0
+            }
+        };
+
+        if arrived(from) {
+            return Some(vec![]);
+        }
+
+        let heuristic =
+            |grid_coords: GridCoords| (grid_coords.x - to.x).abs() + (grid_coords.y - to.y).abs();
+
+        // Ties are broken by insertion order, so that paths don't change between two calls
+        let mut open = BinaryHeap::from([Reverse((heuristic(from), 0, from.x, from.y))]);
+        let mut came_from: HashMap<GridCoords, GridCoords> = HashMap::new();
+        let mut costs: HashMap<GridCoords, i32> = HashMap::from([(from, 0)]);
+        let mut insertions = 0;
+
+        while let Some(Reverse((_, _, x, y))) = open.pop() {
+            let current = GridCoords { x, y };
+
+            if arrived(current) {
+                let mut path = vec![current];
+
+                while let Some(previous) = came_from.get(path.last()?)
+                    && *previous != from
+                {
+                    path.push(*previous);
+                }
+
+                path.reverse();
+
+                return Some(path);
+            }
+
+            let cost = costs[&current] + 1;
+
+            for neighbour in NEIGHBOURS.map(|offset| current + offset) {
+                if !self.is_walkable(&neighbour)
+                    || !one_way_allows(&self.one_ways, &current, &neighbour)
+                    || costs.get(&neighbour).is_some_and(|known| *known <= cost)
+                {
+                    continue;
+                }
+
+                costs.insert(neighbour, cost);
+                came_from.insert(neighbour, current);
+                insertions += 1;
+                open.push(Reverse((
+                    cost + heuristic(neighbour),
+                    insertions,
+                    neighbour.x,
+                    neighbour.y,
+                )));
+            }
+        }
+
+        None
+    }
+}
+
+/// Walks an entity towards its destination, one tile per tick, avoiding what blocks its [`CollisionLayer`].
+/// The position is tweened by whatever already animates `GridCoords` changes, like NPCs
+#[derive(Component, Default, Debug, Clone)]
+pub struct PathFollower {
+    pub destination: GridCoords,
+    /// Stops next to the destination instead of on it
+    pub next_to: bool,
+    path: Vec<GridCoords>,
+}
+
+impl PathFollower {
+    pub fn new(destination: GridCoords) -> Self {
+        Self {
+            destination,
+            next_to: false,
+            path: vec![],
+        }
+    }
+
+    /// Changes the destination, the path being computed on the next step
+    pub fn go_to(&mut self, destination: GridCoords) {
+        self.set_destination(destination, false);
+    }
+
+    /// Same as [`PathFollower::go_to`], stopping next to the destination
+    pub fn go_next_to(&mut self, destination: GridCoords) {
+        self.set_destination(destination, true);
+    }
+
+    pub fn arrived(&self, current: GridCoords) -> bool {
+        let distance =
+            (current.x - self.destination.x).abs() + (current.y - self.destination.y).abs();
+
+        if self.next_to {
+            distance <= 1
+        } else {
+            distance == 0
+        }
+    }
+
+    fn set_destination(&mut self, destination: GridCoords, next_to: bool) {
+        if destination != self.destination || next_to != self.next_to {
+            self.destination = destination;
+            self.next_to = next_to;
+            self.path.clear();
+        }
+    }
+
+    /// Next tile to walk to, the path being recomputed when something stands in the way.
+    /// One way tiles are only checked when computing the path.
+    /// `None` once arrived. Gives up, staying where it is, when the destination can't be reached
+    pub fn next_step(&mut self, nav_grid: &NavGrid, current: GridCoords) -> Option<GridCoords> {
+        if self.arrived(current) {
+            self.path.clear();
+            return None;
+        }
+
+        let blocked = self
+            .path
+            .first()
+            .is_none_or(|next| !nav_grid.is_walkable(next));
+
+        if blocked {
+            let path = if self.next_to {
+                nav_grid.find_path_next_to(current, self.destination)
+            } else {
+                nav_grid.find_path(current, self.destination)
+            };
+
+            let Some(path) = path else {
+                self.go_to(current);
+                return None;
+            };
+
+            self.path = path;
+        }
+
+        if self.path.is_empty() {
+            return None;
+        }
+
+        Some(self.path.remove(0))
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, follow_paths.run_if(in_state(GameState::InGame)));
+}
+
+fn follow_paths(
+    main_tick: Res<MainTick>,
+    level_colliders: Res<LevelColliders>,
+    followers: Query<(&mut GridCoords, &mut PathFollower, &CollisionLayer)>,
+) {
+    if !main_tick.timer.just_finished() || followers.is_empty() {
+        return;
+    }
+
+    let mut nav_grids: HashMap<CollisionLayer, NavGrid> = HashMap::new();
+
+    for (mut grid_coords, mut follower, layer) in followers {
+        let nav_grid = nav_grids
+            .entry(*layer)
+            .or_insert_with(|| NavGrid::for_layer(&level_colliders, *layer));
+
+        if let Some(next) = follower.next_step(nav_grid, *grid_coords) {
+            // Followers of the same layer moving during the same tick don't walk into each other
+            nav_grid.blocked.remove(&*grid_coords);
+            nav_grid.blocked.insert(next);
+
+            *grid_coords = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(x: i32, ys: std::ops::Range<i32>) -> Vec<GridCoords> {
+        ys.map(|y| GridCoords { x, y }).collect()
+    }
+
+    #[test]
+    fn finds_path_around_walls() {
+        // Setup
+        let nav_grid = NavGrid::new(IVec2::new(5, 5), wall(2, 0..4));
+
+        // Run
+        let path = nav_grid.find_path(GridCoords { x: 0, y: 0 }, GridCoords { x: 4, y: 0 });
+
+        // Check
+        let path = path.expect("There should be a path over the wall.");
+
+        assert_eq!(path.len(), 12);
+        assert_eq!(path.last(), Some(&GridCoords { x: 4, y: 0 }));
+        assert!(path.contains(&GridCoords { x: 2, y: 4 }));
+        assert!(
+            path.iter()
+                .all(|grid_coords| nav_grid.is_walkable(grid_coords))
+        );
+    }
+
+    #[test]
+    fn finds_no_path_to_enclosed_tiles() {
+        // Setup
+        let nav_grid = NavGrid::new(IVec2::new(5, 5), wall(2, 0..5));
+
+        // Run
+        let path = nav_grid.find_path(GridCoords { x: 0, y: 0 }, GridCoords { x: 4, y: 0 });
+
+        // Check
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn follows_path_and_reroutes() {
+        // Setup
+        let mut follower = PathFollower::new(GridCoords { x: 3, y: 0 });
+        let nav_grid = NavGrid::new(IVec2::new(4, 2), vec![]);
+
+        // Run
+        let first_step = follower.next_step(&nav_grid, GridCoords { x: 0, y: 0 });
+        let blocked_nav_grid = NavGrid::new(IVec2::new(4, 2), vec![GridCoords { x: 2, y: 0 }]);
+        let second_step = follower.next_step(&blocked_nav_grid, GridCoords { x: 1, y: 0 });
+
+        // Check
+        assert_eq!(first_step, Some(GridCoords { x: 1, y: 0 }));
+        assert_eq!(second_step, Some(GridCoords { x: 1, y: 1 }));
+    }
+}
@@ -4,15 +4,18 @@ use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_tweening::*;
+use serde::{Deserialize, Serialize};
 
+use crate::game::combat::{CombatEnded, CombatStarted};
 use crate::game::controls::{PlayerAction, PlayerInputs};
 use crate::game::dialog_system::{DialogEndedEvent, RunDialogEvent};
 use crate::game::global::GameState;
-use crate::game::physics::colliders::{Collider, LevelColliders};
+use crate::game::physics::colliders::{Collider, CollisionLayer, LevelColliders};
 use crate::game::tick::TickDelta;
+use crate::game::ui::OpenedPanel;
 
 use super::camera::CameraTarget;
-use super::map::GRID_SIZE;
+use super::map::{GRID_SIZE, zones::ZoneOccupant};
 
 pub const JITTER_THRESHOLD: f32 = 0.015;
 const ACTION_Z_DEPTH: f32 = 2.;
@@ -37,21 +40,23 @@ struct PlayerStates {
     action_state: ActionState,
 }
 
-#[derive(Component, Default)]
-enum PlayerStance {
+#[derive(Component, Default, Clone, Copy, PartialEq)]
+pub enum PlayerStance {
     #[default]
     Roaming,
     Talking,
+    Reading,
+    Fighting,
 }
 
 impl PlayerStance {
-    fn to_player_states(&self) -> PlayerStates {
+    fn to_player_states(self) -> PlayerStates {
         match self {
             Self::Roaming => PlayerStates {
                 movement_state: MovementState::Free,
                 action_state: ActionState::Free,
             },
-            Self::Talking => PlayerStates {
+            Self::Talking | Self::Reading | Self::Fighting => PlayerStates {
                 movement_state: MovementState::Locked,
                 action_state: ActionState::Locked,
             },
@@ -65,6 +70,7 @@ pub struct WalkCycleTimer {
 }
 
 #[derive(Default, Component)]
+#[require(ZoneOccupant)]
 pub struct Player;
 
 #[derive(Default, Component, Debug)]
@@ -81,36 +87,70 @@ struct ActionZone {
 #[derive(Component, Debug)]
 struct ActionZoneDisplay;
 
-#[derive(Component)]
-enum Facing {
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Facing {
     North,
     East,
     South,
     West,
 }
 
+impl Facing {
+    /// Reads `north`, `east`, `south` or `west`, in any case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "north" => Some(Self::North),
+            "east" => Some(Self::East),
+            "south" => Some(Self::South),
+            "west" => Some(Self::West),
+            _ => None,
+        }
+    }
+
+    /// Direction of `to` seen from `from`, along the axis they are the furthest apart on
+    fn towards(from: GridCoords, to: GridCoords) -> Option<Self> {
+        let (x, y) = (to.x - from.x, to.y - from.y);
+
+        match (x, y) {
+            (0, 0) => None,
+            _ if x.abs() >= y.abs() => Some(if x > 0 { Self::East } else { Self::West }),
+            _ => Some(if y > 0 { Self::North } else { Self::South }),
+        }
+    }
+}
+
 #[derive(Message)]
 pub struct Teleported {
     pub entity: Entity,
     pub grid_coords: IVec2,
+    pub facing: Option<Facing>,
 }
 
 #[derive(Message)]
 pub struct Activate {
-    pub _entity: Entity,
+    pub entity: Entity,
     pub grid_coords: IVec2,
 }
 
+/// Written on every step of the player, heard by enemies
+#[derive(Message)]
+pub struct Footstep {
+    pub grid_coords: GridCoords,
+}
+
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, init_walk_cycle_timer);
     app.add_message::<Teleported>();
     app.add_message::<Activate>();
+    app.add_message::<Footstep>();
     app.add_systems(
         Update,
         (
             update_walk_cycle_timer,
             set_talking_stance,
             remove_talking_stance,
+            set_reading_stance,
+            set_fighting_stance,
             update_player_states,
             display_action_zone,
             spawn_player,
@@ -137,7 +177,8 @@ fn spawn_player(
         if entity_instance.identifier == "Player" && players.iter().next().is_none() {
             commands.spawn((
                 Player,
-                Collider,
+                Collider::default(),
+                CollisionLayer::PLAYER,
                 CameraTarget,
                 AseSlice {
                     name: "player_idle".into(),
@@ -170,6 +211,13 @@ fn spawn_player(
     }
 }
 
+/// Run condition: the player walks around, out of dialogs, panels and fights
+pub fn player_roaming(players: Query<&PlayerStance, With<Player>>) -> bool {
+    players
+        .iter()
+        .all(|stance| *stance == PlayerStance::Roaming)
+}
+
 fn init_walk_cycle_timer(mut commands: Commands, tick_delta: Res<TickDelta>) {
     commands.insert_resource(WalkCycleTimer {
         timer: Timer::new(Duration::from_secs_f32(tick_delta.note), TimerMode::Once),
@@ -246,6 +294,7 @@ fn player_movement_input(
 }
 
 fn update_player_grid_coords(
+    mut footsteps: MessageWriter<Footstep>,
     mut query: Query<(&mut GridCoords, &Velocity), With<Player>>,
     mut walk_cycle_timer: ResMut<WalkCycleTimer>,
     level_colliders: Res<LevelColliders>,
@@ -257,9 +306,13 @@ fn update_player_grid_coords(
 
         if walk_cycle_timer.timer.remaining_secs() == tick_delta.note
             && !walk_cycle_timer.timer.is_paused()
-            && !level_colliders.in_collider(&destination)
+            && level_colliders.can_move(&player_grid_coords, &destination, CollisionLayer::PLAYER)
         {
             *player_grid_coords = destination;
+
+            footsteps.write(Footstep {
+                grid_coords: destination,
+            });
         } else if walk_cycle_timer.timer.remaining_secs() <= JITTER_THRESHOLD {
             walk_cycle_timer.timer.reset();
             walk_cycle_timer.timer.pause();
@@ -271,12 +324,16 @@ fn update_player_grid_coords(
 
 fn teleport_player(
     mut teleport_event: MessageReader<Teleported>,
-    mut query: Query<(&mut GridCoords, &mut Velocity, &mut Transform), With<Player>>,
+    mut query: Query<(&mut GridCoords, &mut Velocity, &mut Transform, &mut Facing), With<Player>>,
     mut walk_cycle_timer: ResMut<WalkCycleTimer>,
 ) {
     for event in teleport_event.read() {
-        if let Ok((mut player_grid_coords, mut player_velocity, mut player_transform)) =
-            query.get_mut(event.entity)
+        if let Ok((
+            mut player_grid_coords,
+            mut player_velocity,
+            mut player_transform,
+            mut player_facing,
+        )) = query.get_mut(event.entity)
         {
             player_velocity.value = IVec2 {
                 ..Default::default()
@@ -292,6 +349,10 @@ fn teleport_player(
             walk_cycle_timer.timer.pause();
 
             *player_grid_coords = event.grid_coords.into();
+
+            if let Some(facing) = event.facing {
+                *player_facing = facing;
+            }
         }
     }
 }
@@ -403,7 +464,7 @@ fn activate(
         for (entity, action_zone, action_state) in players {
             if let ActionState::Free = action_state {
                 activate_event.write(Activate {
-                    _entity: entity,
+                    entity,
                     grid_coords: action_zone.value,
                 });
             }
@@ -442,7 +503,73 @@ fn remove_talking_stance(
 ) {
     for mut stance in players {
         for _ in dialog_event.read() {
-            *stance = PlayerStance::Roaming
+            // A panel opened during the dialog stays opened
+            if *stance == PlayerStance::Talking {
+                *stance = PlayerStance::Roaming
+            }
+        }
+    }
+}
+
+/// Turns the player to the enemy engaging them
+fn set_fighting_stance(
+    players: Query<(&mut PlayerStance, &mut Facing, &GridCoords), With<Player>>,
+    enemies: Query<&GridCoords, Without<Player>>,
+    mut combat_started: MessageReader<CombatStarted>,
+    mut combat_ended: MessageReader<CombatEnded>,
+) {
+    let enemy = combat_started.read().last().map(|event| event.enemy);
+    let ended = combat_ended.read().count() > 0;
+
+    for (mut stance, mut facing, grid_coords) in players {
+        // A panel opened during the fight stays opened
+        if ended && *stance == PlayerStance::Fighting {
+            *stance = PlayerStance::Roaming;
+        } else if let Some(enemy) = enemy {
+            *stance = PlayerStance::Fighting;
+
+            if let Ok(enemy_grid_coords) = enemies.get(enemy)
+                && let Some(towards) = Facing::towards(*grid_coords, *enemy_grid_coords)
+            {
+                *facing = towards;
+            }
+        }
+    }
+}
+
+/// Locks the player while a panel is opened, then restores the previous stance
+fn set_reading_stance(
+    players: Query<&mut PlayerStance, With<Player>>,
+    opened_panel: Res<OpenedPanel>,
+    mut previous_stance: Local<Option<PlayerStance>>,
+    mut combat_ended: MessageReader<CombatEnded>,
+    mut dialog_ended: MessageReader<DialogEndedEvent>,
+) {
+    // The fight or the dialog ended while reading, nothing to go back to
+    let fight_ended = combat_ended.read().count() > 0;
+    let dialog_ended = dialog_ended.read().count() > 0;
+
+    match *previous_stance {
+        Some(PlayerStance::Fighting) if fight_ended => *previous_stance = None,
+        Some(PlayerStance::Talking) if dialog_ended => *previous_stance = None,
+        _ => (),
+    }
+
+    if !opened_panel.is_changed() {
+        return;
+    }
+
+    for mut stance in players {
+        match (opened_panel.0, *stance) {
+            (Some(_), PlayerStance::Reading) | (None, PlayerStance::Roaming) => (),
+            (Some(_), current_stance) => {
+                *previous_stance = Some(current_stance);
+                *stance = PlayerStance::Reading;
+            }
+            (None, PlayerStance::Reading) => {
+                *stance = previous_stance.take().unwrap_or_default();
+            }
+            (None, PlayerStance::Talking | PlayerStance::Fighting) => (),
         }
     }
 }
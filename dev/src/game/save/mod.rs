@@ -0,0 +1,339 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::game::{
+    controls::{PlayerAction, PlayerInputs},
+    dialog_system::{dialog_log::DialogLog, story_variables::StoryVariables},
+    global::GameState,
+    items::Inventory,
+    journal::Journal,
+    madness::{Checkpoint, Madness, MadnessPhaseChanged},
+    map::{
+        Arrival, ChangeLevel, CurrentLevelInfos,
+        variants::{LevelVariant, switch_variant_level},
+        world_state::WorldState,
+    },
+    player::{Facing, Player, player_roaming},
+};
+
+const SAVE_DIRECTORY: &str = "saves";
+/// Bump this every time [`SaveFile`] changes in a non backward compatible way
+const SAVE_FORMAT_VERSION: u32 = 1;
+/// Slot written and read by the quick save and quick load actions
+const QUICK_SAVE_SLOT: u8 = 0;
+
+/// Writes the current run into the given save slot
+#[derive(Message)]
+pub struct SaveGame {
+    pub slot: u8,
+}
+
+/// Restores the run stored in the given save slot
+#[derive(Message)]
+pub struct LoadGame {
+    pub slot: u8,
+}
+
+/// Only reads the version, so that files written by another format version
+/// can be rejected before being deserialized
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct SaveFile {
+    version: u32,
+    level: String,
+    player: PlayerSave,
+    world_state: WorldState,
+    story_variables: StoryVariables,
+    dialog_log: DialogLog,
+    journal: Journal,
+    checkpoint: Checkpoint,
+    inventory: Inventory,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct PlayerSave {
+    x: i32,
+    y: i32,
+    facing: Facing,
+    madness: f32,
+}
+
+/// Possible errors that can be produced while saving or loading a slot
+#[non_exhaustive]
+#[derive(Debug, Error)]
+enum SaveError {
+    /// An [IO](std::io) Error
+    #[error("Could not access save file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not write save file: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not read save file: {0}")]
+    Deserialize(#[from] ron::error::SpannedError),
+    #[error("Save file version {found} is not supported (expected {expected})")]
+    UnsupportedVersion { found: u32, expected: u32 },
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_message::<SaveGame>();
+    app.add_message::<LoadGame>();
+    app.add_systems(
+        Update,
+        (
+            quick_save_and_load.run_if(player_roaming),
+            save_game,
+            // Before the variant switch, so that the loaded level is not swapped for the sibling
+            // of the variant the player was in before loading
+            load_game.before(switch_variant_level),
+        )
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn slot_path(slot: u8) -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join(format!("slot_{slot}.ron"))
+}
+
+fn serialize_save(save: &SaveFile) -> Result<String, SaveError> {
+    Ok(ron::ser::to_string_pretty(
+        save,
+        ron::ser::PrettyConfig::default(),
+    )?)
+}
+
+fn deserialize_save(content: &str) -> Result<SaveFile, SaveError> {
+    let header: SaveHeader = ron::from_str(content)?;
+
+    if header.version != SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion {
+            found: header.version,
+            expected: SAVE_FORMAT_VERSION,
+        });
+    }
+
+    Ok(ron::from_str(content)?)
+}
+
+fn write_slot(slot: u8, save: &SaveFile) -> Result<(), SaveError> {
+    fs::create_dir_all(SAVE_DIRECTORY)?;
+    fs::write(slot_path(slot), serialize_save(save)?)?;
+
+    Ok(())
+}
+
+fn read_slot(slot: u8) -> Result<SaveFile, SaveError> {
+    deserialize_save(&fs::read_to_string(slot_path(slot))?)
+}
+
+fn quick_save_and_load(
+    keys: Res<PlayerInputs>,
+    mut save_game: MessageWriter<SaveGame>,
+    mut load_game: MessageWriter<LoadGame>,
+) {
+    if keys.just_pressed_actions.contains(&PlayerAction::QuickSave) {
+        save_game.write(SaveGame {
+            slot: QUICK_SAVE_SLOT,
+        });
+    }
+
+    if keys.just_pressed_actions.contains(&PlayerAction::QuickLoad) {
+        load_game.write(LoadGame {
+            slot: QUICK_SAVE_SLOT,
+        });
+    }
+}
+
+fn save_game(
+    mut events: MessageReader<SaveGame>,
+    level_infos: Res<CurrentLevelInfos>,
+    players: Query<(&GridCoords, &Facing, &Madness, &Inventory), With<Player>>,
+    world_state: Res<WorldState>,
+    story_variables: Res<StoryVariables>,
+    dialog_log: Res<DialogLog>,
+    journal: Res<Journal>,
+    checkpoint: Res<Checkpoint>,
+) {
+    for event in events.read() {
+        let Ok((grid_coords, facing, madness, inventory)) = players.single() else {
+            warn!("No player to save in slot {}", event.slot);
+            continue;
+        };
+
+        let save = SaveFile {
+            version: SAVE_FORMAT_VERSION,
+            level: level_infos.identifier.clone(),
+            player: PlayerSave {
+                x: grid_coords.x,
+                y: grid_coords.y,
+                facing: *facing,
+                madness: madness.value,
+            },
+            world_state: world_state.clone(),
+            story_variables: story_variables.clone(),
+            dialog_log: dialog_log.clone(),
+            journal: journal.clone(),
+            checkpoint: checkpoint.clone(),
+            inventory: inventory.clone(),
+        };
+
+        match write_slot(event.slot, &save) {
+            Ok(()) => info!("Game saved in slot {}", event.slot),
+            Err(err) => error!("Could not save slot {}: {}", event.slot, err),
+        }
+    }
+}
+
+/// Runs before the madness systems, the level variant being set from the loaded madness
+/// so that the loaded level is picked in the variant it was saved in
+pub fn load_game(
+    mut events: MessageReader<LoadGame>,
+    mut change_level: MessageWriter<ChangeLevel>,
+    mut phase_changed: MessageWriter<MadnessPhaseChanged>,
+    mut level_variant: ResMut<LevelVariant>,
+    mut world_state: ResMut<WorldState>,
+    mut story_variables: ResMut<StoryVariables>,
+    mut dialog_log: ResMut<DialogLog>,
+    mut journal: ResMut<Journal>,
+    mut checkpoint: ResMut<Checkpoint>,
+    mut players: Query<(&mut Madness, &mut Inventory), With<Player>>,
+) {
+    for event in events.read() {
+        match read_slot(event.slot) {
+            Ok(save) => {
+                // Snapshots are re-applied when the level entities are respawned
+                *world_state = save.world_state;
+                *story_variables = save.story_variables;
+                *dialog_log = save.dialog_log;
+                *journal = save.journal;
+                *checkpoint = save.checkpoint;
+
+                for (mut madness, mut inventory) in &mut players {
+                    let previous = madness.phase();
+                    madness.value = save.player.madness;
+                    *inventory = save.inventory.clone();
+
+                    let phase = madness.phase();
+
+                    level_variant.set_if_neq(LevelVariant(phase.level_variant().map(Into::into)));
+
+                    if phase != previous {
+                        phase_changed.write(MadnessPhaseChanged { previous, phase });
+                    }
+                }
+
+                change_level.write(ChangeLevel {
+                    identifier: save.level,
+                    arrival: Some(Arrival {
+                        grid_coords: ivec2(save.player.x, save.player.y),
+                        facing: Some(save.player.facing),
+                    }),
+                });
+
+                info!("Game loaded from slot {}", event.slot);
+            }
+            Err(err) => error!("Could not load slot {}: {}", event.slot, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::game::{
+        items::InventoryItem, journal::Clue, madness::CheckpointLocation,
+        map::world_state::EntitySnapshot,
+    };
+
+    fn save_file() -> SaveFile {
+        SaveFile {
+            version: SAVE_FORMAT_VERSION,
+            level: "Level_1".into(),
+            player: PlayerSave {
+                x: 3,
+                y: 7,
+                facing: Facing::West,
+                madness: 42.,
+            },
+            world_state: WorldState {
+                entities: HashMap::from([(
+                    "npc-iid".into(),
+                    EntitySnapshot {
+                        dialog_state: Some("{\"flows\":{}}".into()),
+                        grid_coords: Some((4, 2)),
+                        flags: None,
+                    },
+                )]),
+            },
+            story_variables: {
+                let mut story_variables = StoryVariables::default();
+                story_variables.set("met_fishermen", true);
+                story_variables.set("madness_level", 2);
+                story_variables
+            },
+            dialog_log: {
+                let mut dialog_log = DialogLog::default();
+                let conversation = dialog_log.start_conversation();
+                dialog_log.record_line(conversation, "Dummy Npc", "Once upon a time...");
+                dialog_log.record_choice(conversation, "There were two choices.");
+                dialog_log
+            },
+            journal: Journal {
+                clues: vec![Clue {
+                    id: "dummy_clue".into(),
+                    title: "Dummy clue".into(),
+                    description: "Something odd.".into(),
+                    source: "Dummy action".into(),
+                }],
+            },
+            checkpoint: Checkpoint(Some(CheckpointLocation {
+                level: "Level_0".into(),
+                grid_coords: (1, 1),
+            })),
+            inventory: Inventory {
+                items: vec![InventoryItem {
+                    id: "pills".into(),
+                    count: 2,
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn round_trips_save_file() {
+        // Setup
+        let save = save_file();
+
+        // Run
+        let content = serialize_save(&save).unwrap();
+        let loaded = deserialize_save(&content);
+
+        // Check
+        assert_eq!(loaded.unwrap(), save);
+    }
+
+    #[test]
+    fn rejects_other_format_versions() {
+        // Setup
+        let save = SaveFile {
+            version: SAVE_FORMAT_VERSION + 1,
+            ..save_file()
+        };
+        let content = serialize_save(&save).unwrap();
+
+        // Run
+        let loaded = deserialize_save(&content);
+
+        // Check
+        assert!(matches!(loaded, Err(SaveError::UnsupportedVersion { .. })));
+    }
+}
@@ -17,6 +17,14 @@ pub struct MainTickCounter {
     pub value: u8,
 }
 
+impl MainTickCounter {
+    /// Whether the current note is the first of a measure of the game tempo
+    pub fn on_measure(&self, game_tempo: &GameTempo) -> bool {
+        self.value
+            .is_multiple_of((game_tempo.notes_per_measure.round() as u8).max(1))
+    }
+}
+
 /// Stores the duration of the different divisions rhythming the game
 #[derive(Resource)]
 pub struct TickDelta {
@@ -0,0 +1,76 @@
+use bevy::{color::palettes::css::GRAY, prelude::*};
+
+use crate::game::{
+    dialog_system::dialog_log::{DialogLog, DialogLogKind},
+    global::GameState,
+    ui::{OpenedPanel, Panel},
+};
+
+#[derive(Component)]
+struct DialogLogEntries;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::InGame), spawn_dialog_log);
+    app.add_systems(
+        Update,
+        fill_dialog_log
+            .after(super::toggle_panels)
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn spawn_dialog_log(mut commands: Commands) {
+    super::spawn_panel(&mut commands, Panel::DialogLog, DialogLogEntries);
+}
+
+/// Rebuilds the entries every time the panel opens, scrolled to the latest ones
+fn fill_dialog_log(
+    mut commands: Commands,
+    opened_panel: Res<OpenedPanel>,
+    dialog_log: Res<DialogLog>,
+    entries: Single<(Entity, &mut ScrollPosition), With<DialogLogEntries>>,
+) {
+    if !opened_panel.is_changed() || opened_panel.0 != Some(Panel::DialogLog) {
+        return;
+    }
+
+    let (entries, mut scroll_position) = entries.into_inner();
+
+    commands.entity(entries).despawn_related::<Children>();
+
+    for group in dialog_log.grouped_by_speaker() {
+        let speaker = match group[0].kind {
+            DialogLogKind::Line => group[0].speaker.clone(),
+            DialogLogKind::Choice => "You".into(),
+        };
+
+        commands.entity(entries).with_children(|parent| {
+            parent.spawn((
+                Text::new(speaker),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(GRAY.into()),
+                Node {
+                    margin: UiRect::top(px(super::DEFAULT_PADDING)),
+                    ..Default::default()
+                },
+            ));
+
+            for entry in group {
+                parent.spawn((
+                    Text::new(entry.text.clone()),
+                    TextFont {
+                        font_size: super::DEFAULT_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+            }
+        });
+    }
+
+    // Clamped to the content height by the layout
+    scroll_position.y = f32::MAX;
+}
@@ -10,10 +10,13 @@ use bevy::{
 };
 
 use crate::game::{
+    audio::object_audio::PlayObjectAudio,
     controls::{PlayerAction, PlayerInputs},
-    dialog_system::{DialogChoice, DialogEndedEvent, DisplayCurrentDialogEvent, RunDialogEvent},
+    dialog_system::{
+        DialogChoice, DialogEndedEvent, DialogLine, DisplayCurrentDialogEvent, RunDialogEvent,
+    },
     global::GameState,
-    ui::InputSelected,
+    ui::{InputSelected, no_panel_opened},
 };
 
 #[derive(Component)]
@@ -35,7 +38,7 @@ struct DialogChoiceIndex(usize);
 struct DialogContainer;
 
 #[derive(Component, Default)]
-struct CurrentDialogLines(HashMap<u8, String>);
+struct CurrentDialogLines(HashMap<u8, DialogLine>);
 
 #[derive(Component, Default)]
 struct CurrentDialogChoices(Vec<DialogChoice>);
@@ -43,8 +46,13 @@ struct CurrentDialogChoices(Vec<DialogChoice>);
 #[derive(Component, Default)]
 struct CurrentDialogChoiceIndex(Option<usize>);
 
+/// Portrait of the current line, with the plain portrait to fall back on
+/// when the line emotion has no image
 #[derive(Component, Default)]
-struct CurrentDialogImage(Handle<Image>);
+struct CurrentDialogImage {
+    image: Handle<Image>,
+    fallback: Option<Handle<Image>>,
+}
 
 #[derive(Component, Default)]
 struct CurrentDialogSourceName(String);
@@ -61,15 +69,16 @@ pub fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
-            clean_dialog_container,
+            clean_dialog_container.run_if(no_panel_opened),
             set_dialog_cache,
+            fall_back_to_plain_portrait,
             update_image,
             update_source_name,
             set_dialog_line,
-            update_dialog_choices,
+            update_dialog_choices.run_if(no_panel_opened),
             highlight_focused_element,
-            update_dialog_line,
-            fetch_next_dialog_block.run_if(dialog_end_reached),
+            update_dialog_line.run_if(no_panel_opened),
+            fetch_next_dialog_block.run_if(dialog_end_reached.and(no_panel_opened)),
             end_dialog,
         )
             .run_if(in_state(GameState::InGame))
@@ -129,7 +138,7 @@ fn spawn_dialog_box(mut commands: Commands, asset_server: Res<AssetServer>) {
 fn spawn_dialog_cache(mut commands: Commands) {
     commands.spawn((
         CurrentDialogLines(Default::default()),
-        CurrentDialogImage(Default::default()),
+        CurrentDialogImage::default(),
         CurrentDialogSourceName(Default::default()),
         CurrentDialogChoices(Default::default()),
         CurrentSourceEntity(Default::default()),
@@ -141,30 +150,17 @@ fn set_dialog_cache(
     mut dialog_events: MessageReader<DisplayCurrentDialogEvent>,
     dialog_infos: Query<(
         &mut CurrentDialogLines,
-        &mut CurrentDialogImage,
-        &mut CurrentDialogSourceName,
         &mut CurrentDialogChoices,
         &mut CurrentSourceEntity,
     )>,
     dialog_container: Single<&mut Node, With<DialogContainer>>,
-    asset_server: Res<AssetServer>,
 ) {
     let mut container = dialog_container.into_inner();
 
-    for (
-        mut dialog_lines,
-        mut dialog_image,
-        mut dialog_source_name,
-        mut dialog_choices,
-        mut current_entity,
-    ) in dialog_infos
-    {
+    for (mut dialog_lines, mut dialog_choices, mut current_entity) in dialog_infos {
         for event in dialog_events.read() {
             container.display = Display::Block;
 
-            dialog_image.0 = asset_server.load(event.image_path.clone());
-            dialog_source_name.0 = event.source_name.clone();
-
             current_entity.0 = Some(event.source_entity);
 
             for (index, line) in event.lines.iter().enumerate() {
@@ -202,7 +198,21 @@ fn update_image(
     let dialog_image = dialog_image.into_inner();
     let mut image_node = image_node.into_inner();
 
-    image_node.image = dialog_image.0.clone();
+    image_node.image = dialog_image.image.clone();
+}
+
+fn fall_back_to_plain_portrait(
+    dialog_image: Single<&mut CurrentDialogImage>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut dialog_image = dialog_image.into_inner();
+
+    if dialog_image.fallback.is_some()
+        && asset_server.load_state(&dialog_image.image).is_failed()
+        && let Some(fallback) = dialog_image.fallback.take()
+    {
+        dialog_image.image = fallback;
+    }
 }
 
 fn update_source_name(
@@ -217,62 +227,115 @@ fn update_source_name(
 
 fn set_dialog_line(
     mut commands: Commands,
-    dialog_lines: Single<&mut CurrentDialogLines, Changed<CurrentDialogLines>>,
+    dialog_infos: Single<
+        (
+            &mut CurrentDialogLines,
+            &mut CurrentDialogImage,
+            &mut CurrentDialogSourceName,
+            &CurrentSourceEntity,
+        ),
+        Changed<CurrentDialogLines>,
+    >,
     dialog_container: Single<Entity, With<DialogContainer>>,
+    asset_server: Res<AssetServer>,
 ) {
     let dialog_container = dialog_container.into_inner();
-    let mut dialog_lines = dialog_lines.into_inner();
+    let (mut dialog_lines, mut dialog_image, mut dialog_source_name, source_entity) =
+        dialog_infos.into_inner();
 
     if let Some(key) = dialog_lines.0.clone().keys().min()
         && *key == 0
+        && let Some(line) = dialog_lines.0.remove(key)
     {
-        let node = commands
-            .spawn((
-                Text::new(dialog_lines.0[key].clone()),
-                TextFont {
-                    font_size: super::DEFAULT_FONT_SIZE,
-                    ..default()
-                },
-                TextColor(BLACK.into()),
-                DialogLinesUi,
-            ))
-            .id();
-
-        commands.entity(dialog_container).add_child(node);
-
-        dialog_lines.0.remove(key);
+        display_line(
+            &mut commands,
+            dialog_container,
+            line,
+            source_entity,
+            &mut dialog_image,
+            &mut dialog_source_name,
+            &asset_server,
+        );
     }
 }
 
 fn update_dialog_line(
     mut commands: Commands,
-    dialog_lines: Single<&mut CurrentDialogLines>,
+    dialog_infos: Single<(
+        &mut CurrentDialogLines,
+        &mut CurrentDialogImage,
+        &mut CurrentDialogSourceName,
+        &CurrentSourceEntity,
+    )>,
     dialog_container: Single<Entity, With<DialogContainer>>,
     keys: Res<PlayerInputs>,
+    asset_server: Res<AssetServer>,
 ) {
     let dialog_container = dialog_container.into_inner();
-    let mut dialog_lines = dialog_lines.into_inner();
+    let (mut dialog_lines, mut dialog_image, mut dialog_source_name, source_entity) =
+        dialog_infos.into_inner();
 
     if keys.just_pressed_actions.contains(&PlayerAction::Activate)
         && !dialog_lines.0.is_empty()
         && let Some(key) = dialog_lines.0.clone().keys().min()
+        && let Some(line) = dialog_lines.0.remove(key)
     {
-        let node = commands
-            .spawn((
-                Text::new(dialog_lines.0[key].clone()),
-                TextFont {
-                    font_size: super::DEFAULT_FONT_SIZE,
-                    ..default()
-                },
-                TextColor(BLACK.into()),
-                DialogLinesUi,
-            ))
-            .id();
-
-        commands.entity(dialog_container).add_child(node);
-
-        dialog_lines.0.remove(key);
+        display_line(
+            &mut commands,
+            dialog_container,
+            line,
+            source_entity,
+            &mut dialog_image,
+            &mut dialog_source_name,
+            &asset_server,
+        );
+    };
+}
+
+/// Spawns the line text, shows its speaker with the portrait of its emotion and plays its sound
+fn display_line(
+    commands: &mut Commands,
+    dialog_container: Entity,
+    line: DialogLine,
+    source_entity: &CurrentSourceEntity,
+    dialog_image: &mut CurrentDialogImage,
+    dialog_source_name: &mut CurrentDialogSourceName,
+    asset_server: &AssetServer,
+) {
+    *dialog_image = match line.emotion_image_path() {
+        // Narrations have no portrait
+        _ if line.image_path.is_empty() => CurrentDialogImage::default(),
+        Some(emotion_image_path) => CurrentDialogImage {
+            image: asset_server.load(emotion_image_path),
+            fallback: Some(asset_server.load(&line.image_path)),
+        },
+        None => CurrentDialogImage {
+            image: asset_server.load(&line.image_path),
+            fallback: None,
+        },
     };
+
+    let node = commands
+        .spawn((
+            Text::new(line.text),
+            TextFont {
+                font_size: super::DEFAULT_FONT_SIZE,
+                ..default()
+            },
+            TextColor(BLACK.into()),
+            DialogLinesUi,
+        ))
+        .id();
+
+    commands.entity(dialog_container).add_child(node);
+
+    dialog_source_name.0 = line.speaker;
+
+    if let Some(audio_id) = line.sfx
+        && let Some(entity) = source_entity.0
+    {
+        commands.write_message(PlayObjectAudio { entity, audio_id });
+    }
 }
 
 fn dialog_end_reached(choices: Query<&DialogChoiceUi>, lines: Query<&DialogLinesUi>) -> bool {
@@ -389,6 +452,6 @@ fn end_dialog(
         container.display = Display::None;
         source_entity.0 = Default::default();
         dialog_source_name.0 = Default::default();
-        dialog_image.0 = Default::default();
+        *dialog_image = Default::default();
     }
 }
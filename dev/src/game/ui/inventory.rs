@@ -0,0 +1,143 @@
+use bevy::{
+    color::palettes::css::GRAY,
+    input_focus::{
+        InputFocus, InputFocusVisible, directional_navigation::DirectionalNavigationMap,
+    },
+    math::CompassOctant,
+    prelude::*,
+};
+
+use crate::game::{
+    global::GameState,
+    items::{Inventory, ItemDefinitions, ItemDefinitionsHandle, ItemUsed, UseItem},
+    player::Player,
+    ui::{InputSelected, OpenedPanel, Panel},
+};
+
+#[derive(Component)]
+struct InventoryItems;
+
+/// Focusable entry of the inventory, used when selected
+#[derive(Component)]
+struct InventorySlot(String);
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::InGame), spawn_inventory);
+    app.add_systems(
+        Update,
+        (fill_inventory, highlight_focused_slot)
+            .chain()
+            .after(super::toggle_panels)
+            .run_if(in_state(GameState::InGame)),
+    );
+    app.add_observer(use_selected_item);
+}
+
+fn spawn_inventory(mut commands: Commands) {
+    super::spawn_panel(&mut commands, Panel::Inventory, InventoryItems);
+}
+
+/// Rebuilds the slots when the inventory opens and after an item is used,
+/// as selecting a slot clears the navigation map
+fn fill_inventory(
+    mut commands: Commands,
+    mut item_used: MessageReader<ItemUsed>,
+    mut directional_nav_map: ResMut<DirectionalNavigationMap>,
+    mut input_focus: ResMut<InputFocus>,
+    opened_panel: Res<OpenedPanel>,
+    definitions_handle: Res<ItemDefinitionsHandle>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    inventory: Single<&Inventory, With<Player>>,
+    items: Single<Entity, With<InventoryItems>>,
+) {
+    let item_used = item_used.read().count() > 0;
+
+    if opened_panel.0 != Some(Panel::Inventory) || !(opened_panel.is_changed() || item_used) {
+        return;
+    }
+
+    let items = items.into_inner();
+
+    commands.entity(items).despawn_related::<Children>();
+    directional_nav_map.clear();
+
+    let mut slots: Vec<Entity> = vec![];
+
+    commands.entity(items).with_children(|parent| {
+        if inventory.items.is_empty() {
+            parent.spawn((
+                Text::new("Nothing in your pockets."),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(GRAY.into()),
+            ));
+        }
+
+        for item in &inventory.items {
+            let Some(definition) = definitions
+                .get(&definitions_handle.0)
+                .and_then(|definitions| definitions.0.get(&item.id))
+            else {
+                continue;
+            };
+
+            let slot = parent
+                .spawn((
+                    Text::new(format!("{} x{}", definition.name, item.count)),
+                    TextFont {
+                        font_size: super::DEFAULT_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        margin: UiRect::top(px(super::DEFAULT_PADDING)),
+                        ..Default::default()
+                    },
+                    InventorySlot(item.id.clone()),
+                ))
+                .id();
+
+            parent.spawn((
+                Text::new(definition.description.clone()),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(GRAY.into()),
+            ));
+
+            slots.push(slot);
+        }
+    });
+
+    if let Some(first_slot) = slots.first() {
+        directional_nav_map.add_looping_edges(&slots, CompassOctant::South);
+        input_focus.set(*first_slot);
+    }
+}
+
+fn highlight_focused_slot(
+    input_focus: Res<InputFocus>,
+    input_focus_visible: Res<InputFocusVisible>,
+    mut query: Query<(Entity, &mut TextFont), With<InventorySlot>>,
+) {
+    for (entity, mut text) in query.iter_mut() {
+        if input_focus.0 == Some(entity) && input_focus_visible.0 {
+            text.font_size = super::DEFAULT_FONT_SIZE + 5.;
+        } else {
+            text.font_size = super::DEFAULT_FONT_SIZE;
+        }
+    }
+}
+
+fn use_selected_item(
+    event: On<InputSelected>,
+    mut use_item: MessageWriter<UseItem>,
+    slots: Query<&InventorySlot>,
+) {
+    if let Ok(slot) = slots.get(event.entity) {
+        use_item.write(UseItem { id: slot.0.clone() });
+    }
+}
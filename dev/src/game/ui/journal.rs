@@ -0,0 +1,88 @@
+use bevy::{color::palettes::css::GRAY, prelude::*};
+
+use crate::game::{
+    global::GameState,
+    journal::Journal,
+    ui::{OpenedPanel, Panel},
+};
+
+#[derive(Component)]
+struct JournalClues;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::InGame), spawn_journal);
+    app.add_systems(
+        Update,
+        fill_journal
+            .after(super::toggle_panels)
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn spawn_journal(mut commands: Commands) {
+    super::spawn_panel(&mut commands, Panel::Journal, JournalClues);
+}
+
+/// Rebuilds the clues every time the journal opens
+fn fill_journal(
+    mut commands: Commands,
+    opened_panel: Res<OpenedPanel>,
+    journal: Res<Journal>,
+    clues: Single<(Entity, &mut ScrollPosition), With<JournalClues>>,
+) {
+    if !opened_panel.is_changed() || opened_panel.0 != Some(Panel::Journal) {
+        return;
+    }
+
+    let (clues, mut scroll_position) = clues.into_inner();
+
+    commands.entity(clues).despawn_related::<Children>();
+
+    commands.entity(clues).with_children(|parent| {
+        if journal.clues.is_empty() {
+            parent.spawn((
+                Text::new("No clue yet."),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(GRAY.into()),
+            ));
+        }
+
+        for clue in &journal.clues {
+            parent.spawn((
+                Text::new(clue.title.clone()),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::top(px(super::DEFAULT_PADDING)),
+                    ..Default::default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new(clue.description.clone()),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent.spawn((
+                Text::new(format!("Source: {}", clue.source)),
+                TextFont {
+                    font_size: super::DEFAULT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(GRAY.into()),
+            ));
+        }
+    });
+
+    scroll_position.y = 0.;
+}
@@ -9,13 +9,179 @@ use bevy::{
     prelude::*,
 };
 
-use crate::game::controls::{PlayerAction, PlayerInputs};
+use crate::game::{
+    controls::{PlayerAction, PlayerInputs},
+    global::GameState,
+};
 
+mod dialog_log;
 pub mod dialogs;
+mod inventory;
+mod journal;
 mod menu;
+mod notifications;
 
 pub const DEFAULT_FONT_SIZE: f32 = 45.;
 pub const DEFAULT_PADDING: u8 = 25;
+/// Scrolled distance each frame the player holds up or down in a panel
+const SCROLL_SPEED: f32 = 15.;
+
+/// Full screen panels shown over the game, the player can't move while one is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    DialogLog,
+    Journal,
+    Inventory,
+}
+
+impl Panel {
+    /// Input opening and closing the panel
+    fn action(&self) -> PlayerAction {
+        match self {
+            Self::DialogLog => PlayerAction::DialogLog,
+            Self::Journal => PlayerAction::Journal,
+            Self::Inventory => PlayerAction::Inventory,
+        }
+    }
+
+    fn title(&self) -> Option<&'static str> {
+        match self {
+            Self::DialogLog => None,
+            Self::Journal => Some("Journal"),
+            Self::Inventory => Some("Inventory"),
+        }
+    }
+
+    /// Browsed like the dialog choices instead of scrolled
+    fn navigable(&self) -> bool {
+        matches!(self, Self::Inventory)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct OpenedPanel(pub Option<Panel>);
+
+/// Root node of a panel, only displayed while it is opened
+#[derive(Component)]
+struct PanelContainer(Panel);
+
+/// Node holding the entries of a panel
+#[derive(Component)]
+struct PanelContent(Panel);
+
+pub fn no_panel_opened(opened_panel: Res<OpenedPanel>) -> bool {
+    opened_panel.0.is_none()
+}
+
+fn navigable(opened_panel: Res<OpenedPanel>) -> bool {
+    opened_panel.0.is_none_or(|panel| panel.navigable())
+}
+
+/// Spawns a hidden panel, `content` marking the node its module fills with entries
+fn spawn_panel(commands: &mut Commands, panel: Panel, content: impl Bundle) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: px(0),
+                bottom: px(0),
+                left: px(0),
+                right: px(0),
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(px(DEFAULT_PADDING)),
+                ..Default::default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.85)),
+            PanelContainer(panel),
+        ))
+        .with_children(|parent| {
+            if let Some(title) = panel.title() {
+                parent.spawn((
+                    Text::new(title),
+                    TextFont {
+                        font_size: DEFAULT_FONT_SIZE + 10.,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+            }
+
+            let content_node = if panel.navigable() {
+                Node {
+                    width: percent(100),
+                    flex_direction: FlexDirection::Column,
+                    ..Default::default()
+                }
+            } else {
+                // Fills the rest of the panel, scrolling through the entries
+                Node {
+                    width: percent(100),
+                    min_height: px(0),
+                    flex_grow: 1.,
+                    flex_direction: FlexDirection::Column,
+                    overflow: Overflow::scroll_y(),
+                    ..Default::default()
+                }
+            };
+
+            parent.spawn((content_node, PanelContent(panel), content));
+        });
+}
+
+/// Opens the panel of the pressed input when none is, or closes it
+fn toggle_panels(
+    keys: Res<PlayerInputs>,
+    mut opened_panel: ResMut<OpenedPanel>,
+    mut directional_nav_map: ResMut<DirectionalNavigationMap>,
+    mut input_focus: ResMut<InputFocus>,
+    containers: Query<(&PanelContainer, &mut Node)>,
+) {
+    for (container, mut node) in containers {
+        let panel = container.0;
+
+        if !keys.just_pressed_actions.contains(&panel.action()) {
+            continue;
+        }
+
+        match opened_panel.0 {
+            None => {
+                opened_panel.0 = Some(panel);
+                node.display = Display::Flex;
+            }
+            Some(opened) if opened == panel => {
+                opened_panel.0 = None;
+                node.display = Display::None;
+
+                if panel.navigable() {
+                    directional_nav_map.clear();
+                    input_focus.clear();
+                }
+            }
+            Some(_) => (),
+        }
+    }
+}
+
+fn scroll_panels(
+    keys: Res<PlayerInputs>,
+    opened_panel: Res<OpenedPanel>,
+    contents: Query<(&PanelContent, &mut ScrollPosition)>,
+) {
+    for (content, mut scroll_position) in contents {
+        if opened_panel.0 != Some(content.0) || content.0.navigable() {
+            continue;
+        }
+
+        if keys.pressed_actions.contains(&PlayerAction::Up) {
+            scroll_position.y = (scroll_position.y - SCROLL_SPEED).max(0.);
+        }
+
+        if keys.pressed_actions.contains(&PlayerAction::Down) {
+            scroll_position.y += SCROLL_SPEED;
+        }
+    }
+}
 
 #[derive(EntityEvent)]
 struct InputSelected {
@@ -28,9 +194,23 @@ pub fn plugin(app: &mut App) {
         DirectionalNavigationPlugin,
         menu::plugin,
         dialogs::plugin,
+        dialog_log::plugin,
+        journal::plugin,
+        inventory::plugin,
+        notifications::plugin,
     ));
+    app.init_resource::<OpenedPanel>();
     app.insert_resource(InputFocusVisible(true));
-    app.add_systems(Update, (navigate, interact_with_focused_input));
+    app.add_systems(
+        Update,
+        (navigate, interact_with_focused_input).run_if(navigable),
+    );
+    app.add_systems(
+        Update,
+        (toggle_panels, scroll_panels)
+            .chain()
+            .run_if(in_state(GameState::InGame)),
+    );
 }
 
 fn navigate(
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::game::{
+    combat::{AttackJudged, CombatEnded, Judgement},
+    global::GameState,
+    items::{ItemDefinitions, ItemDefinitionsHandle, ItemPickedUp, ItemUsed},
+    journal::ClueAcquired,
+    madness::{MadnessPhase, MadnessPhaseChanged},
+    map::zones::{ZoneEntered, ZoneExited, madness_zones::MadnessZone},
+    player::Player,
+};
+
+/// Time a notification stays on screen
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
+
+/// Column holding the notifications, in the top right corner of the screen
+#[derive(Component)]
+struct Notifications;
+
+/// Short text telling the player what just happened, despawned once its timer is finished
+#[derive(Component)]
+struct Notification(Timer);
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::InGame), spawn_notifications);
+    app.add_systems(
+        Update,
+        (
+            notify_clues,
+            notify_items,
+            notify_combat,
+            notify_waking_up,
+            notify_madness_zones,
+            expire_notifications,
+        )
+            .run_if(in_state(GameState::InGame)),
+    );
+}
+
+fn spawn_notifications(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(super::DEFAULT_PADDING),
+            right: px(super::DEFAULT_PADDING),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            ..Default::default()
+        },
+        Notifications,
+    ));
+}
+
+fn notify(commands: &mut Commands, notifications: Entity, text: String) {
+    commands.entity(notifications).with_child((
+        Text::new(text),
+        TextFont {
+            font_size: super::DEFAULT_FONT_SIZE,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Notification(Timer::new(NOTIFICATION_DURATION, TimerMode::Once)),
+    ));
+}
+
+fn notify_clues(
+    mut commands: Commands,
+    mut clue_acquired: MessageReader<ClueAcquired>,
+    notifications: Single<Entity, With<Notifications>>,
+) {
+    for event in clue_acquired.read() {
+        notify(
+            &mut commands,
+            *notifications,
+            format!("New clue: {}", event.clue.title),
+        );
+    }
+}
+
+fn notify_items(
+    mut commands: Commands,
+    mut item_picked_up: MessageReader<ItemPickedUp>,
+    mut item_used: MessageReader<ItemUsed>,
+    definitions_handle: Res<ItemDefinitionsHandle>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    notifications: Single<Entity, With<Notifications>>,
+) {
+    let name = |id: &str| {
+        definitions
+            .get(&definitions_handle.0)
+            .and_then(|definitions| definitions.0.get(id))
+            .map_or(id.to_string(), |definition| definition.name.clone())
+    };
+
+    for event in item_picked_up.read() {
+        notify(
+            &mut commands,
+            *notifications,
+            format!("Picked up: {}", name(&event.id)),
+        );
+    }
+
+    for event in item_used.read() {
+        notify(
+            &mut commands,
+            *notifications,
+            format!("Used: {}", name(&event.id)),
+        );
+    }
+}
+
+fn notify_combat(
+    mut commands: Commands,
+    mut attack_judged: MessageReader<AttackJudged>,
+    mut combat_ended: MessageReader<CombatEnded>,
+    notifications: Single<Entity, With<Notifications>>,
+) {
+    for event in attack_judged.read() {
+        let text = match event.judgement {
+            Judgement::Perfect => "Perfect!",
+            Judgement::Good => "Good",
+            Judgement::Miss => "Miss",
+        };
+
+        notify(&mut commands, *notifications, text.into());
+    }
+
+    // Losing is told by fainting
+    for event in combat_ended.read() {
+        if event.victory {
+            notify(&mut commands, *notifications, "Enemy defeated".into());
+        }
+    }
+}
+
+fn notify_waking_up(
+    mut commands: Commands,
+    mut phase_changed: MessageReader<MadnessPhaseChanged>,
+    notifications: Single<Entity, With<Notifications>>,
+) {
+    for event in phase_changed.read() {
+        if event.previous == MadnessPhase::Faint {
+            notify(
+                &mut commands,
+                *notifications,
+                "You wake up, head pounding".into(),
+            );
+        }
+    }
+}
+
+/// Tells the player when their head starts spinning in a zone raising the madness, and when it clears up
+fn notify_madness_zones(
+    mut commands: Commands,
+    mut zone_entered: MessageReader<ZoneEntered<MadnessZone>>,
+    mut zone_exited: MessageReader<ZoneExited<MadnessZone>>,
+    notifications: Single<Entity, With<Notifications>>,
+    players: Query<(), With<Player>>,
+) {
+    for event in zone_entered.read() {
+        if players.contains(event.entity) && event.zone.raises_madness() {
+            notify(
+                &mut commands,
+                *notifications,
+                "Your head starts spinning".into(),
+            );
+        }
+    }
+
+    for event in zone_exited.read() {
+        if players.contains(event.entity) && event.zone.raises_madness() {
+            notify(&mut commands, *notifications, "Your head clears up".into());
+        }
+    }
+}
+
+fn expire_notifications(
+    mut commands: Commands,
+    time: Res<Time>,
+    notifications: Query<(Entity, &mut Notification)>,
+) {
+    for (entity, mut notification) in notifications {
+        if notification.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
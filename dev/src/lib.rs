@@ -1,14 +1,26 @@
+// Benchmarks rely on the nightly `test` crate
+#![cfg_attr(test, feature(test))]
+
 use bevy::{
-    asset::load_internal_binary_asset,
+    asset::{io::AssetSourceBuilder, load_internal_binary_asset},
     prelude::*,
     window::{WindowMode, WindowResolution},
 };
 
+#[cfg(test)]
+extern crate test;
+
 mod game;
 pub struct AppPlugin;
 
 impl Plugin for AppPlugin {
     fn build(&self, app: &mut App) {
+        // Dialogs are compiled from the writers' files, asset sources must exist before `AssetPlugin`
+        app.register_asset_source(
+            game::SCENARIO_SOURCE,
+            AssetSourceBuilder::platform_default(game::SCENARIO_PATH, None),
+        );
+
         app.add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
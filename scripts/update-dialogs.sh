@@ -1,3 +0,0 @@
-#!/bin/sh
-
-cp scenario/dialogs/*.ink.json dev/assets/dialogs/